@@ -8,7 +8,7 @@ const PlayerTwo = ({ result }) => {
   const [score, setScore] = useState(0);
   const [copied, setCopied] = useState(false);
   const { id } = useParams();
-  const { isHost, chainId, game, opponentScore, opponentChainId, lastRoundRecord } =
+  const { isHost, chainId, matchId, game, opponentScore, opponentChainId, lastRoundRecord } =
     useContext(LineraContext);
   const isMatchmaking = id === "matchmaking";
 
@@ -35,7 +35,7 @@ const PlayerTwo = ({ result }) => {
   }, [isHost, lastRoundRecord, opponentScore, result]);
 
   const handleCopyRoomId = async () => {
-    const text = String(chainId || "");
+    const text = String(matchId || "");
     if (!text) return;
 
     try {
@@ -92,14 +92,14 @@ const PlayerTwo = ({ result }) => {
                   type="button"
                   className={`${styles.copy_button} ${copied ? styles.copied : ""}`}
                   onClick={handleCopyRoomId}
-                  disabled={!chainId}
+                  disabled={!matchId}
                   aria-label={copied ? "copied" : "copy room id"}
                   title={copied ? "copied" : "copy"}
                 >
                   {copied ? "✓" : "📋"}
                 </button>
               </div>
-              <span className={styles.room_id_value}>{chainId}</span>
+              <span className={styles.room_id_value}>{matchId}</span>
             </div>
           )}
         </div>
@@ -120,35 +120,85 @@ const normalizeEnumKey = (value) =>
     .toLowerCase()
     .replace(/[^a-z0-9]/g, "");
 
-const choiceEnumFallback = (value) => {
-  const v = normalizeChoiceText(value);
-  if (v === "stone") return "Stone";
-  if (v === "paper") return "Paper";
-  if (v === "scissors") return "Scissors";
-  return null;
-};
+// The service encodes a move as its index in the match's `rules.moves`.
+const CLASSIC_MOVES = ["Stone", "Paper", "Scissors"];
 
-const mapChoiceToServerEnum = (value, enumNames) => {
+const choiceIndex = (value, moves) => {
   const normalized = normalizeChoiceText(value);
   if (!normalized) return null;
-  const candidates =
-    normalized === "scissors" ? ["scissors", "scissor"] : [normalized];
-  const names = Array.isArray(enumNames) ? enumNames : [];
-  const match = names.find((n) => candidates.includes(normalizeEnumKey(n)));
-  return match || null;
+  const list = Array.isArray(moves) && moves.length ? moves : CLASSIC_MOVES;
+  const index = list.findIndex((m) => normalizeChoiceText(m) === normalized);
+  return index >= 0 ? index : null;
+};
+
+const choiceName = (value, moves) => {
+  if (typeof value !== "number") return value;
+  const list = Array.isArray(moves) && moves.length ? moves : CLASSIC_MOVES;
+  return list[value] ?? null;
 };
 
-const normalizeRoundRecord = (record) => {
+const normalizeRoundRecord = (record, moves) => {
   if (!record) return record;
-  const host = normalizeChoiceText(record.hostChoice);
-  const guest = normalizeChoiceText(record.guestChoice);
+  const hostName = choiceName(record.hostChoice, moves);
+  const guestName = choiceName(record.guestChoice, moves);
+  const host = normalizeChoiceText(hostName);
+  const guest = normalizeChoiceText(guestName);
   return {
     ...record,
-    hostChoice: host ? host[0].toUpperCase() + host.slice(1) : record.hostChoice,
-    guestChoice: guest ? guest[0].toUpperCase() + guest.slice(1) : record.guestChoice,
+    hostChoice: host ? host[0].toUpperCase() + host.slice(1) : hostName,
+    guestChoice: guest ? guest[0].toUpperCase() + guest.slice(1) : guestName,
   };
 };
 
+// The salt hides a committed choice until both players are ready, so it must survive reloads.
+const commitStorageKey = (matchId) => `sps_commit:${String(matchId || "")}`;
+
+const generateSalt = () => {
+  const bytes = new Uint8Array(16);
+  crypto.getRandomValues(bytes);
+  return Array.from(bytes, (b) => b.toString(16).padStart(2, "0")).join("");
+};
+
+const saveCommit = (matchId, commit) => {
+  try {
+    localStorage.setItem(commitStorageKey(matchId), JSON.stringify(commit));
+  } catch { }
+};
+
+const loadCommit = (matchId) => {
+  try {
+    const raw = localStorage.getItem(commitStorageKey(matchId));
+    return raw ? JSON.parse(raw) : null;
+  } catch {
+    return null;
+  }
+};
+
+const clearCommit = (matchId) => {
+  try {
+    localStorage.removeItem(commitStorageKey(matchId));
+  } catch { }
+};
+
+const matchCounter = (matchId) => {
+  const m = String(matchId || "").match(/-(\d+)$/);
+  return m ? Number.parseInt(m[1], 10) : -1;
+};
+
+const pickCurrentMatchId = (matches) => {
+  const list = Array.isArray(matches) ? matches : [];
+  const rank = (m) => {
+    const status = normalizeEnumKey(m?.status);
+    if (status === "active") return 0;
+    if (status === "waitingforplayer") return 1;
+    return 2;
+  };
+  const sorted = [...list].sort(
+    (a, b) => rank(a) - rank(b) || matchCounter(b?.matchId) - matchCounter(a?.matchId)
+  );
+  return sorted[0]?.matchId ?? null;
+};
+
 const defaultPlayerName = (chainId) => {
   if (!chainId) return "Player";
   return `Player-${String(chainId).slice(0, 6)}`;
@@ -165,6 +215,7 @@ const LineraContextProvider = ({ children }) => {
   const [syncUnlocked, setSyncUnlocked] = useState(true);
   const [finalResult, setFinalResult] = useState(null);
 
+  const [matchId, setMatchId] = useState(null);
   const [game, setGame] = useState(null);
   const [matchStatus, setMatchStatus] = useState(null);
   const [isHost, setIsHost] = useState(false);
@@ -184,7 +235,9 @@ const LineraContextProvider = ({ children }) => {
   const appRef = useRef(null);
   const notificationUnsubRef = useRef(null);
   const refreshInFlightRef = useRef(false);
-  const choiceEnumNamesRef = useRef(null);
+  const matchIdRef = useRef(null);
+  const revealedRoundRef = useRef(null);
+  const movesRef = useRef(CLASSIC_MOVES);
   const syncMinHeightRef = useRef(0);
   const refreshDebounceTimerRef = useRef(null);
   const lastSnapshotRef = useRef({});
@@ -202,22 +255,29 @@ const LineraContextProvider = ({ children }) => {
     return data?.data;
   }, []);
 
-  const loadChoiceEnumNames = useCallback(async () => {
-    try {
-      const data = await gql(`query { __type(name: "Choice") { enumValues { name } } }`);
-      const names = data?.__type?.enumValues?.map((v) => v?.name).filter(Boolean) || [];
-      if (names.length) {
-        choiceEnumNamesRef.current = names;
+  const revealCommittedChoice = useCallback(
+    async (targetMatchId, round) => {
+      const key = `${targetMatchId}:${round}`;
+      if (revealedRoundRef.current === key) return;
+      const commit = loadCommit(targetMatchId);
+      if (!commit?.salt || !Number.isInteger(commit.choice)) return;
+      revealedRoundRef.current = key;
+      const id = escapeGqlString(targetMatchId);
+      const salt = escapeGqlString(commit.salt);
+      try {
+        await gql(`mutation { revealChoice(matchId: "${id}", choice: ${commit.choice}, salt: "${salt}") }`);
+      } catch (e) {
+        revealedRoundRef.current = null;
+        throw e;
       }
-      return names;
-    } catch {
-      return choiceEnumNamesRef.current || [];
-    }
-  }, [gql]);
+    },
+    [gql]
+  );
 
   const refresh = useCallback(async () => {
     if (!ready) return;
     if (!syncUnlocked) {
+      setMatchId(null);
       setGame(null);
       setMatchStatus(null);
       setIsHost(false);
@@ -237,75 +297,104 @@ const LineraContextProvider = ({ children }) => {
     try {
       const data = await gql(`
         query {
-          game {
-            matchId
-            hostChainId
-            status
-            players { chainId name }
-            round
-            hostScore
-            guestScore
-            lastOutcome
-            winnerChainId
-            history { round hostChoice guestChoice outcome hostScore guestScore timestamp }
-          }
-          matchStatus
-          isHost
-          opponentChainId
-          myReady
-          opponentReady
-          myChoice
-          opponentChoice
-          myScore
-          opponentScore
-          lastRoundRecord { round hostChoice guestChoice outcome hostScore guestScore timestamp }
-          roundHistory { round hostChoice guestChoice outcome hostScore guestScore timestamp }
+          matches { matchId status }
           lastNotification
         }
       `);
-      const nextGame = data?.game ?? null;
+
+      // Stick to the selected match even once it is archived, so its final state stays visible.
+      if (!matchIdRef.current) {
+        matchIdRef.current = pickCurrentMatchId(data?.matches);
+      }
+      const currentMatchId = matchIdRef.current;
+      setMatchId((prev) => (Object.is(prev, currentMatchId) ? prev : currentMatchId));
+
+      let matchData = null;
+      if (currentMatchId) {
+        const id = escapeGqlString(currentMatchId);
+        matchData = await gql(`
+          query {
+            game(matchId: "${id}") {
+              matchId
+              hostChainId
+              status
+              players { chainId name }
+              round
+              rules { moves }
+              hostScore
+              guestScore
+              lastOutcome
+              winnerChainId
+              history { round hostChoice guestChoice outcome hostScore guestScore timestamp }
+            }
+            myReady(matchId: "${id}")
+            opponentReady(matchId: "${id}")
+            myChoice(matchId: "${id}")
+            opponentChoice(matchId: "${id}")
+          }
+        `);
+      }
+
+      const nextGame = matchData?.game ?? null;
+      const moves = nextGame?.rules?.moves?.length ? nextGame.rules.moves : CLASSIC_MOVES;
+      movesRef.current = moves;
       const nextGameJson = JSON.stringify(nextGame);
       if (nextGameJson !== lastSnapshotRef.current.gameJson) {
         lastSnapshotRef.current.gameJson = nextGameJson;
         setGame(nextGame);
       }
 
-      const nextMatchStatus = data?.matchStatus ?? null;
+      const nextMatchStatus = nextGame?.status ?? null;
       setMatchStatus((prev) => (Object.is(prev, nextMatchStatus) ? prev : nextMatchStatus));
 
-      const nextIsHost = Boolean(data?.isHost);
+      // Derived from the game itself, since archived matches only answer `game`.
+      const mine = String(chainId || "");
+      const nextIsHost = Boolean(nextGame) && String(nextGame.hostChainId || "") === mine;
       setIsHost((prev) => (prev === nextIsHost ? prev : nextIsHost));
 
-      const nextOpponentChainId = data?.opponentChainId ?? null;
+      const players = Array.isArray(nextGame?.players) ? nextGame.players : [];
+      const opponent = players.find((p) => String(p?.chainId || "") !== mine);
+      const nextOpponentChainId = opponent?.chainId ?? null;
       setOpponentChainId((prev) => (Object.is(prev, nextOpponentChainId) ? prev : nextOpponentChainId));
 
-      const nextMyReady = Boolean(data?.myReady);
+      const nextMyReady = Boolean(matchData?.myReady);
       setMyReady((prev) => (prev === nextMyReady ? prev : nextMyReady));
 
-      const nextOpponentReady = Boolean(data?.opponentReady);
+      const nextOpponentReady = Boolean(matchData?.opponentReady);
       setOpponentReady((prev) => (prev === nextOpponentReady ? prev : nextOpponentReady));
 
+      // Once both commitments are in, reveal ours with the salt kept since the pick.
+      if (nextMyReady && nextOpponentReady && matchData?.myChoice == null) {
+        revealCommittedChoice(currentMatchId, nextGame?.round).catch((e) => {
+          setLastNotification(String(e?.message || e));
+        });
+      }
+
       const nextMyChoice =
-        normalizeRoundRecord({ hostChoice: data?.myChoice })?.hostChoice ?? null;
+        normalizeRoundRecord({ hostChoice: matchData?.myChoice }, moves)?.hostChoice ?? null;
       setMyChoice((prev) => (Object.is(prev, nextMyChoice) ? prev : nextMyChoice));
 
       const nextOpponentChoice =
-        normalizeRoundRecord({ hostChoice: data?.opponentChoice })?.hostChoice ?? null;
+        normalizeRoundRecord({ hostChoice: matchData?.opponentChoice }, moves)?.hostChoice ?? null;
       setOpponentChoice((prev) => (Object.is(prev, nextOpponentChoice) ? prev : nextOpponentChoice));
 
-      const nextMyScore = Number(data?.myScore ?? 0);
+      const nextMyScore = Number((nextIsHost ? nextGame?.hostScore : nextGame?.guestScore) ?? 0);
       setMyScore((prev) => (prev === nextMyScore ? prev : nextMyScore));
 
-      const nextOpponentScore = Number(data?.opponentScore ?? 0);
+      const nextOpponentScore = Number(
+        (nextIsHost ? nextGame?.guestScore : nextGame?.hostScore) ?? 0
+      );
       setOpponentScore((prev) => (prev === nextOpponentScore ? prev : nextOpponentScore));
 
-      if (nextMyScore >= 3 || nextOpponentScore >= 3) {
+      if (normalizeEnumKey(nextMatchStatus) === "ended") {
         const nextMatchId = nextGame?.matchId ?? null;
+        const nextWinnerChainId = nextGame?.winnerChainId ?? null;
         setFinalResult((prev) => {
           const nextSnapshot = {
             matchId: nextMatchId,
             myScore: nextMyScore,
             opponentScore: nextOpponentScore,
+            winnerChainId: nextWinnerChainId,
           };
           const prevJson = prev ? JSON.stringify(prev) : "";
           const nextJson = JSON.stringify(nextSnapshot);
@@ -313,16 +402,17 @@ const LineraContextProvider = ({ children }) => {
         });
       }
 
-      const nextLastRoundRecord = normalizeRoundRecord(data?.lastRoundRecord ?? null);
+      const history = Array.isArray(nextGame?.history) ? nextGame.history : [];
+      const nextLastRoundRecord = history.length
+        ? normalizeRoundRecord(history[history.length - 1], moves)
+        : null;
       const nextLastRoundRecordJson = JSON.stringify(nextLastRoundRecord);
       if (nextLastRoundRecordJson !== lastSnapshotRef.current.lastRoundRecordJson) {
         lastSnapshotRef.current.lastRoundRecordJson = nextLastRoundRecordJson;
         setLastRoundRecord(nextLastRoundRecord);
       }
 
-      const nextRoundHistory = Array.isArray(data?.roundHistory)
-        ? data.roundHistory.map(normalizeRoundRecord)
-        : [];
+      const nextRoundHistory = history.map((record) => normalizeRoundRecord(record, moves));
       const nextRoundHistoryJson = JSON.stringify(nextRoundHistory);
       if (nextRoundHistoryJson !== lastSnapshotRef.current.roundHistoryJson) {
         lastSnapshotRef.current.roundHistoryJson = nextRoundHistoryJson;
@@ -338,17 +428,15 @@ const LineraContextProvider = ({ children }) => {
     } finally {
       refreshInFlightRef.current = false;
     }
-  }, [gql, ready, syncUnlocked]);
+  }, [chainId, gql, ready, revealCommittedChoice, syncUnlocked]);
 
   const snapshotFinalResult = useCallback((snapshot) => {
     if (!snapshot) return;
-    const my = Number(snapshot.myScore ?? 0);
-    const opp = Number(snapshot.opponentScore ?? 0);
-    if (my < 3 && opp < 3) return;
     const nextSnapshot = {
       matchId: snapshot.matchId ?? null,
-      myScore: my,
-      opponentScore: opp,
+      myScore: Number(snapshot.myScore ?? 0),
+      opponentScore: Number(snapshot.opponentScore ?? 0),
+      winnerChainId: snapshot.winnerChainId ?? null,
     };
     setFinalResult((prev) => {
       const prevJson = prev ? JSON.stringify(prev) : "";
@@ -555,16 +643,21 @@ const LineraContextProvider = ({ children }) => {
     async (hostName) => {
       const name = escapeGqlString(hostName || defaultPlayerName(chainId));
       await gql(`mutation { createMatch(hostName: "${name}") }`);
+      matchIdRef.current = null;
       await refresh();
     },
     [chainId, gql, refresh]
   );
 
   const joinMatch = useCallback(
-    async (hostChainId, playerName) => {
+    async (hostChainId, targetMatchId, playerName) => {
       const host = escapeGqlString(hostChainId);
+      const id = escapeGqlString(targetMatchId);
       const name = escapeGqlString(playerName || defaultPlayerName(chainId));
-      await gql(`mutation { joinMatch(hostChainId: "${host}", playerName: "${name}") }`);
+      await gql(
+        `mutation { joinMatch(hostChainId: "${host}", matchId: "${id}", playerName: "${name}") }`
+      );
+      matchIdRef.current = targetMatchId;
       await refresh();
     },
     [chainId, gql, refresh]
@@ -576,6 +669,7 @@ const LineraContextProvider = ({ children }) => {
       const name = escapeGqlString(playerName || defaultPlayerName(chainId));
 
       await gql(`mutation { searchPlayer(orchestratorChainId: "${orchestrator}", playerName: "${name}") }`);
+      matchIdRef.current = null;
       await refresh();
     },
     [chainId, gql, refresh]
@@ -583,31 +677,26 @@ const LineraContextProvider = ({ children }) => {
 
   const pickAndReady = useCallback(
     async (value) => {
-      const names = choiceEnumNamesRef.current || (await loadChoiceEnumNames());
-      const serverChoice =
-        mapChoiceToServerEnum(value, names) || choiceEnumFallback(value);
-      if (!serverChoice) throw new Error("Invalid choice");
-      try {
-        await gql(`mutation { pickAndReady(choice: ${serverChoice}) }`);
-      } catch (e) {
-        const msg = String(e?.message || e);
-        if (msg.includes('enumeration type "Choice" does not contain the value')) {
-          const refreshed = await loadChoiceEnumNames();
-          const retryChoice =
-            mapChoiceToServerEnum(value, refreshed) || choiceEnumFallback(value);
-          if (!retryChoice) throw e;
-          await gql(`mutation { pickAndReady(choice: ${retryChoice}) }`);
-        } else {
-          throw e;
-        }
-      }
+      const choice = choiceIndex(value, movesRef.current);
+      if (choice == null) throw new Error("Invalid choice");
+      const currentMatchId = matchIdRef.current;
+      if (!currentMatchId) throw new Error("No match");
+      const id = escapeGqlString(currentMatchId);
+      const salt = generateSalt();
+      saveCommit(currentMatchId, { choice, salt });
+      await gql(`mutation { pickAndReady(matchId: "${id}", choice: ${choice}, salt: "${salt}") }`);
       await refresh();
     },
-    [gql, loadChoiceEnumNames, refresh]
+    [gql, refresh]
   );
 
   const leaveMatch = useCallback(async () => {
-    await gql(`mutation { leaveMatch }`);
+    const currentMatchId = matchIdRef.current;
+    if (currentMatchId) {
+      await gql(`mutation { leaveMatch(matchId: "${escapeGqlString(currentMatchId)}") }`);
+      clearCommit(currentMatchId);
+    }
+    matchIdRef.current = null;
     await refresh();
   }, [gql, refresh]);
 
@@ -623,6 +712,7 @@ const LineraContextProvider = ({ children }) => {
       syncUnlocked,
       finalResult,
       matchmakerChainId: MATCHMAKER_CHAIN_ID,
+      matchId,
       game,
       matchStatus,
       isHost,
@@ -659,6 +749,7 @@ const LineraContextProvider = ({ children }) => {
       joinMatch,
       lastNotification,
       lastRoundRecord,
+      matchId,
       matchStatus,
       myChoice,
       myReady,
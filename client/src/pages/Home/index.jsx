@@ -6,11 +6,14 @@ import styles from "./styles.module.css";
 
 const PLAYER_NAME_STORAGE_KEY = "sps_player_name";
 
+// Match ids are `<host chain id>-<counter>`, so a room id also names its host.
+const hostChainIdFromMatchId = (matchId) => String(matchId || "").replace(/-\d+$/, "");
+
 const Home = () => {
   const navigate = useNavigate();
   const { ready, initError, chainId, createMatch, searchPlayer } = useContext(LineraContext);
   const [friendMenuOpen, setFriendMenuOpen] = useState(false);
-  const [hostChainIdInput, setHostChainIdInput] = useState("");
+  const [roomIdInput, setRoomIdInput] = useState("");
   const [playerName, setPlayerName] = useState(() => {
     try {
       return localStorage.getItem(PLAYER_NAME_STORAGE_KEY) || "";
@@ -24,17 +27,23 @@ const Home = () => {
     [playerName]
   );
 
+  const normalizedRoomId = useMemo(
+    () => String(roomIdInput || "").trim(),
+    [roomIdInput]
+  );
+
   const normalizedHostChainId = useMemo(
-    () => String(hostChainIdInput || "").trim(),
-    [hostChainIdInput]
+    () => hostChainIdFromMatchId(normalizedRoomId),
+    [normalizedRoomId]
   );
 
   const canJoin = useMemo(() => {
     if (!ready) return false;
+    if (!/-\d+$/.test(normalizedRoomId)) return false;
     if (!normalizedHostChainId) return false;
     if (normalizedHostChainId === "matchmaking") return false;
     return true;
-  }, [normalizedHostChainId, ready]);
+  }, [normalizedHostChainId, normalizedRoomId, ready]);
 
   const canOpenMenus = normalizedPlayerName.length > 0;
 
@@ -110,7 +119,7 @@ const Home = () => {
                 <div className={styles.section}>
                   <div className={styles.section_title}>CREATE ROOM</div>
                   <div className={styles.section_hint}>
-                    Your room id: <span className={styles.mono}>{chainId}</span>
+                    Host chain: <span className={styles.mono}>{chainId}</span>
                   </div>
                   <Button
                     name="Create Room"
@@ -127,13 +136,13 @@ const Home = () => {
                 <div className={styles.section}>
                   <div className={styles.section_title}>JOIN ROOM</div>
                   <div className={styles.section_hint}>
-                    Enter host room id and join.
+                    Enter the room id shown to the host and join.
                   </div>
                   <input
                     className={styles.input}
-                    value={hostChainIdInput}
-                    onChange={(e) => setHostChainIdInput(e.target.value)}
-                    placeholder="Host chain id"
+                    value={roomIdInput}
+                    onChange={(e) => setRoomIdInput(e.target.value)}
+                    placeholder="Room id"
                   />
                   <Button
                     name="Join Room"
@@ -141,9 +150,10 @@ const Home = () => {
                     onClick={() => {
                       if (!canJoin) return;
                       setFriendMenuOpen(false);
+                      const params = new URLSearchParams({ match: normalizedRoomId });
                       const name = normalizedPlayerName;
-                      const q = name ? `?name=${encodeURIComponent(name)}` : "";
-                      navigate(`/room/${normalizedHostChainId}${q}`);
+                      if (name) params.set("name", name);
+                      navigate(`/room/${normalizedHostChainId}?${params.toString()}`);
                     }}
                   />
                 </div>
@@ -6,18 +6,21 @@ import styles from "./styles.module.css";
 
 const Result = () => {
   const navigate = useNavigate();
-  const { ready, finalResult, myScore, opponentScore, leaveMatch } = useContext(LineraContext);
+  const { ready, chainId, finalResult, myScore, opponentScore, leaveMatch } =
+    useContext(LineraContext);
 
   const derivedScores = useMemo(() => {
-    if (finalResult && (Number(finalResult.myScore) >= 3 || Number(finalResult.opponentScore) >= 3)) {
+    if (finalResult) {
       return { mine: Number(finalResult.myScore ?? 0), opp: Number(finalResult.opponentScore ?? 0) };
     }
     return { mine: Number(myScore ?? 0), opp: Number(opponentScore ?? 0) };
   }, [finalResult, myScore, opponentScore]);
 
-  const didWin = useMemo(() => {
-    return derivedScores.mine >= 3;
-  }, [derivedScores.mine]);
+  const outcome = useMemo(() => {
+    const winner = finalResult?.winnerChainId;
+    if (!winner) return "draw";
+    return winner === chainId ? "win" : "lose";
+  }, [chainId, finalResult?.winnerChainId]);
 
   if (!ready) {
     return (
@@ -31,7 +34,9 @@ const Result = () => {
     <div className={styles.container}>
       <div className={styles.result_card}>
         <div className={styles.title}>
-          {didWin ? "🎉 YOU WIN! 🎉" : "😔 YOU LOSE 😔"}
+          {outcome === "win" && "🎉 YOU WIN! 🎉"}
+          {outcome === "lose" && "😔 YOU LOSE 😔"}
+          {outcome === "draw" && "🤝 DRAW 🤝"}
         </div>
         <div className={styles.scores}>
           <div className={styles.score_item}>
@@ -31,6 +31,7 @@ const Room = () => {
     game,
     isHost,
     opponentChainId,
+    matchId,
     matchStatus,
     myScore,
     opponentScore,
@@ -74,9 +75,13 @@ const Room = () => {
       return;
     }
 
+    // Matchmaking guests are synced by the host and have nothing to join.
+    const params = new URLSearchParams(location.search || "");
+    const requestedMatchId = String(params.get("match") || "").trim();
+    if (!requestedMatchId) return;
+
     if (hasJoinedRef.current) return;
     hasJoinedRef.current = true;
-    const params = new URLSearchParams(location.search || "");
     let playerName = String(params.get("name") || "").trim();
     if (!playerName) {
       try {
@@ -85,7 +90,7 @@ const Room = () => {
         playerName = "";
       }
     }
-    joinMatch(id, playerName || undefined).catch(() => {
+    joinMatch(id, requestedMatchId, playerName || undefined).catch(() => {
       hasJoinedRef.current = false;
       navigate("/");
     });
@@ -97,9 +102,7 @@ const Room = () => {
     if (!syncUnlocked) return;
     if (resultNavTriggeredRef.current) return;
 
-    const endedByScore = Number(myScore) >= 3 || Number(opponentScore) >= 3;
-    const endedByStatus = normalizeEnumKey(game?.status || matchStatus) === "ended";
-    const ended = endedByScore || endedByStatus;
+    const ended = normalizeEnumKey(game?.status || matchStatus) === "ended";
     if (!ended) return;
 
     const round = lastRoundRecord?.round;
@@ -114,9 +117,7 @@ const Room = () => {
     id,
     lastRoundRecord?.round,
     matchStatus,
-    myScore,
     navigate,
-    opponentScore,
     ready,
     syncUnlocked,
   ]);
@@ -162,21 +163,32 @@ const Room = () => {
       text = isHost ? "lose" : "win";
     }
 
-    const isTerminalByScore = Number(myScore) >= 3 || Number(opponentScore) >= 3;
+    const isTerminal = normalizeEnumKey(game?.status) === "ended";
 
     (async () => {
       await performAnimation(text);
-      if (!isTerminalByScore) return;
+      if (!isTerminal) return;
       snapshotFinalResult?.({
         matchId: game?.matchId ?? null,
         myScore,
         opponentScore,
+        winnerChainId: game?.winnerChainId ?? null,
       });
       if (resultNavTriggeredRef.current) return;
       resultNavTriggeredRef.current = true;
       navigate("/result");
     })();
-  }, [game?.matchId, isHost, lastRoundRecord, myScore, navigate, opponentScore, snapshotFinalResult]);
+  }, [
+    game?.matchId,
+    game?.status,
+    game?.winnerChainId,
+    isHost,
+    lastRoundRecord,
+    myScore,
+    navigate,
+    opponentScore,
+    snapshotFinalResult,
+  ]);
 
   if (!ready) {
     return (
@@ -217,7 +229,7 @@ const Room = () => {
         {!opponentChainId && (
           <div className={styles.waiting}>
             <div className={styles.waiting_text}>Waiting for opponent to join...</div>
-            <div className={styles.room_id}>Room ID: {chainId}</div>
+            {matchId && <div className={styles.room_id}>Room ID: {matchId}</div>}
           </div>
         )}
       </div>
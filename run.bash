@@ -78,7 +78,8 @@ echo ">>> Publishing and creating application..."
 LINERA_APPLICATION_ID=$(linera --wait-for-outgoing-messages \
   publish-and-create \
   /build/stone-paper-scissors/target/wasm32-unknown-unknown/release/sps_contract.wasm \
-  /build/stone-paper-scissors/target/wasm32-unknown-unknown/release/sps_service.wasm)
+  /build/stone-paper-scissors/target/wasm32-unknown-unknown/release/sps_service.wasm \
+  --json-parameters '{}')
 export REACT_APP_LINERA_APPLICATION_ID=$LINERA_APPLICATION_ID
 
 echo ">>> Creating client .env file..."
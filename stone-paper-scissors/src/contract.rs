@@ -3,11 +3,19 @@
 mod state;
 
 use stone_paper_scissors::{
-    round_outcome, Choice, CrossChainMessage, Game, MatchStatus, Operation, PlayerInfo, RoundRecord, SpsAbi, InstantiationArgument, SpsParameters
+    choice_commitment, elo_rating, rating_window, room_code_hash, round_outcome, Choice,
+    CrossChainMessage, Game, GameResult, GameRules, InstantiationArgument, Invitation, JoinError,
+    JoinRejection, LobbyListing, MatchEvent, MatchFormat, MatchStatus, MatchmakingPlayer, Operation,
+    Pairing, PendingMatch, PlayerInfo, PlayerRating, RatingChange, ReadyCheck, RematchRequest,
+    RoundOutcome, RoundRecord, RoundState, SpsAbi, SpsParameters, Tournament, TournamentKind,
+    TournamentStatus, DEFAULT_INVITATION_TTL_SECS, MATCHMAKING_ENTRY_TTL_MICROS, MATCH_STREAM_NAME,
+    MAX_BRACKET_SIZE, READY_CHECK_WINDOW_MICROS,
 };
 
 use linera_sdk::{
-    linera_base_types::{ChainId, WithContractAbi},
+    linera_base_types::{
+        Account, AccountOwner, Amount, ChainId, StreamName, StreamUpdate, WithContractAbi,
+    },
     views::{RootView, View},
     Contract, ContractRuntime,
 };
@@ -38,11 +46,181 @@ impl SpsContract {
             .and_then(|p| p.chain_id.parse().ok())
     }
 
-    fn reset_round_local_state(&mut self) {
-        self.state.my_ready.set(false);
-        self.state.opponent_ready.set(false);
-        self.state.my_choice.set(None);
-        self.state.opponent_choice.set(None);
+    /// Whether the message being executed was sent by `chain_id`, as reported by the runtime.
+    fn sent_by(&mut self, chain_id: ChainId) -> bool {
+        self.runtime.message_origin_chain_id() == Some(chain_id)
+    }
+
+    /// Whether the message was sent by the host of `game` to one of its players.
+    fn sent_by_host(&mut self, game: &Game) -> bool {
+        let self_chain = self.runtime.chain_id().to_string();
+        let Ok(host) = game.host_chain_id.parse() else {
+            return false;
+        };
+        game.players.iter().any(|p| p.chain_id == self_chain) && self.sent_by(host)
+    }
+
+    /// Whether the message was sent by our opponent in `game`, and it claims to be from
+    /// `declared`.
+    fn sent_by_opponent(&mut self, game: &Game, declared: ChainId) -> bool {
+        self.opponent_chain_id(game) == Some(declared) && self.sent_by(declared)
+    }
+
+    /// Whether the message was sent by an orchestrator allowed by the application parameters.
+    fn sent_by_orchestrator(&mut self) -> bool {
+        let parameters = self.runtime.application_parameters();
+        self.runtime
+            .message_origin_chain_id()
+            .is_some_and(|origin| parameters.is_orchestrator(origin))
+    }
+
+    async fn game(&self, match_id: &str) -> Option<Game> {
+        self.state
+            .matches
+            .get(match_id)
+            .await
+            .expect("Failed to read match")
+    }
+
+    async fn round_state(&self, match_id: &str) -> RoundState {
+        self.state
+            .rounds
+            .get(match_id)
+            .await
+            .expect("Failed to read round state")
+            .unwrap_or_default()
+    }
+
+    fn save_game(&mut self, game: &Game) {
+        self.state
+            .matches
+            .insert(&game.match_id, game.clone())
+            .expect("Failed to store match");
+    }
+
+    fn save_round_state(&mut self, match_id: &str, round_state: RoundState) {
+        self.state
+            .rounds
+            .insert(match_id, round_state)
+            .expect("Failed to store round state");
+    }
+
+    /// Forgets this chain's progress in the current round, along with any draw offer, which
+    /// only stands until the round resolves.
+    fn reset_round_local_state(&mut self, match_id: &str) {
+        self.state
+            .rounds
+            .remove(match_id)
+            .expect("Failed to reset round state");
+        self.state
+            .draw_offers
+            .remove(match_id)
+            .expect("Failed to clear draw offer");
+        self.state
+            .offered_draws
+            .remove(match_id)
+            .expect("Failed to clear draw offer");
+    }
+
+    fn remove_match(&mut self, match_id: &str) {
+        self.state
+            .matches
+            .remove(match_id)
+            .expect("Failed to remove match");
+        self.reset_round_local_state(match_id);
+    }
+
+    /// Match ids are unique per host chain, and prefixed with it so that a guest can take part
+    /// in matches hosted on several chains.
+    fn next_match_id(&mut self) -> String {
+        let counter = *self.state.match_counter.get();
+        self.state.match_counter.set(counter + 1);
+        format!("{}-{}", self.runtime.chain_id(), counter)
+    }
+
+    /// Asks `host` to let this chain join `match_id`, remembering the host so that only it can
+    /// reject the request.
+    fn request_join(
+        &mut self,
+        host: ChainId,
+        match_id: String,
+        player_name: String,
+        stake: Amount,
+        room_code: Option<String>,
+    ) {
+        self.state
+            .pending_joins
+            .insert(&match_id, host.to_string())
+            .expect("Failed to store pending join");
+        let player_chain_id = self.runtime.chain_id();
+        self.runtime.send_message(
+            host,
+            CrossChainMessage::JoinRequest {
+                match_id,
+                player_chain_id,
+                player_name,
+                stake,
+                room_code,
+            },
+        );
+    }
+
+    /// Builds a fresh game hosted on this chain. The match becomes active as soon as it has
+    /// two players.
+    fn new_game(
+        &mut self,
+        players: Vec<PlayerInfo>,
+        format: MatchFormat,
+        rules: GameRules,
+        round_timeout_secs: Option<u64>,
+    ) -> Game {
+        if !format.is_valid() {
+            panic!("Invalid match format");
+        }
+        let status = if players.len() == 2 {
+            MatchStatus::Active
+        } else {
+            MatchStatus::WaitingForPlayer
+        };
+        let round_timeout_secs = round_timeout_secs
+            .unwrap_or_else(|| self.runtime.application_parameters().default_round_timeout_secs);
+        let mut game = Game {
+            match_id: self.next_match_id(),
+            host_chain_id: self.runtime.chain_id().to_string(),
+            status,
+            format,
+            rules,
+            players,
+            round: 1,
+            host_score: 0,
+            guest_score: 0,
+            last_round: None,
+            last_host_choice: None,
+            last_guest_choice: None,
+            last_outcome: None,
+            history: Vec::new(),
+            winner_chain_id: None,
+            round_timeout_micros: round_timeout_secs.saturating_mul(1_000_000),
+            round_deadline_micros: None,
+            orchestrator_chain_id: None,
+            stake: Amount::ZERO,
+            lobby_chain_id: None,
+            room_code_hash: None,
+            tournament_id: None,
+        };
+        if status == MatchStatus::Active {
+            self.start_round_clock(&mut game);
+        }
+        game
+    }
+
+    fn start_round_clock(&mut self, game: &mut Game) {
+        game.round_deadline_micros = (game.round_timeout_micros > 0).then(|| {
+            self.runtime
+                .system_time()
+                .micros()
+                .saturating_add(game.round_timeout_micros)
+        });
     }
 
     fn can_play(&self, game: &Game) -> bool {
@@ -61,13 +239,850 @@ impl SpsContract {
             (opponent_choice, my_choice)
         }
     }
+
+    /// Resolves the current round on the host once both choices are revealed, and syncs the
+    /// updated game to the guest. A reveal that did not match its commitment forfeits the round.
+    async fn try_resolve_round(&mut self, game: Game, round_state: &RoundState) {
+        let Some(my_choice) = round_state.my_choice else {
+            return;
+        };
+        let Some(opponent_choice) = round_state.opponent_choice else {
+            return;
+        };
+
+        let (host_choice, guest_choice) =
+            self.host_and_guest_choices(&game, my_choice, opponent_choice);
+
+        let forfeited_by = if round_state.opponent_forfeit {
+            self.opponent_chain_id(&game).map(|c| c.to_string())
+        } else {
+            None
+        };
+        let outcome = if forfeited_by.is_some() {
+            RoundOutcome::HostWins
+        } else {
+            round_outcome(&game.rules, host_choice, guest_choice)
+        };
+
+        self.apply_round(
+            game,
+            Some(host_choice),
+            Some(guest_choice),
+            outcome,
+            forfeited_by,
+            false,
+        )
+        .await;
+    }
+
+    /// Records a finished round, ends the match if its format says so (or if `ends_match` is
+    /// set) and otherwise starts the clock for the next round. The updated game is synced to
+    /// the opponent.
+    async fn apply_round(
+        &mut self,
+        mut game: Game,
+        host_choice: Option<Choice>,
+        guest_choice: Option<Choice>,
+        outcome: RoundOutcome,
+        forfeited_by: Option<String>,
+        ends_match: bool,
+    ) {
+        let round = game.round;
+        game.last_round = Some(round);
+        game.last_host_choice = host_choice;
+        game.last_guest_choice = guest_choice;
+        game.last_outcome = Some(outcome);
+
+        match outcome {
+            RoundOutcome::HostWins => game.host_score = game.host_score.saturating_add(1),
+            RoundOutcome::GuestWins => game.guest_score = game.guest_score.saturating_add(1),
+            RoundOutcome::Draw => {}
+        }
+
+        game.history.push(RoundRecord {
+            round,
+            host_choice,
+            guest_choice,
+            outcome,
+            host_score: game.host_score,
+            guest_score: game.guest_score,
+            timestamp: self.runtime.system_time().micros().to_string(),
+            forfeited_by,
+        });
+        if game.history.len() > 50 {
+            let excess = game.history.len() - 50;
+            game.history.drain(0..excess);
+        }
+
+        let result = if ends_match {
+            Some(outcome)
+        } else {
+            game.format.result(game.host_score, game.guest_score, round)
+        };
+        if let Some(result) = result {
+            game.status = MatchStatus::Ended;
+            game.round_deadline_micros = None;
+            game.winner_chain_id = match result {
+                RoundOutcome::HostWins => Some(game.host_chain_id.clone()),
+                RoundOutcome::GuestWins => game
+                    .players
+                    .iter()
+                    .find(|p| p.chain_id != game.host_chain_id)
+                    .map(|p| p.chain_id.clone()),
+                RoundOutcome::Draw => None,
+            };
+        } else {
+            game.round = game.round.saturating_add(1);
+            self.start_round_clock(&mut game);
+        }
+
+        if let Some(opponent) = self.opponent_chain_id(&game) {
+            self.runtime
+                .send_message(opponent, CrossChainMessage::GameSync { game: game.clone() });
+        }
+
+        if game.status == MatchStatus::Ended {
+            self.finish_match(game).await;
+        } else {
+            self.save_game(&game);
+            self.reset_round_local_state(&game.match_id);
+            self.publish(MatchEvent::RoundResolved { game });
+        }
+    }
+
+    /// Publishes a match event for spectators. Only the host publishes its matches.
+    fn publish(&mut self, event: MatchEvent) {
+        if !self.is_host(event.game()) {
+            return;
+        }
+        self.runtime.emit(StreamName::from(MATCH_STREAM_NAME), &event);
+    }
+
+    /// Wraps up a match that is over: the host reports the result, and the game moves from
+    /// the live matches into this chain's archive.
+    async fn finish_match(&mut self, mut game: Game) {
+        if game.status == MatchStatus::Ended {
+            self.report_result(&game);
+        }
+        self.delist(&mut game);
+        self.clear_invitations(&game.match_id).await;
+        self.settle_stakes(&game);
+        self.publish(MatchEvent::MatchEnded { game: game.clone() });
+        self.archive_match(game).await;
+    }
+
+    /// Ends an active match without playing it out, on a resignation or an agreed draw. Only
+    /// the host ends matches; the guest learns the result from the game sync.
+    async fn end_match_early(&mut self, mut game: Game, winner_chain_id: Option<String>) {
+        game.status = MatchStatus::Ended;
+        game.round_deadline_micros = None;
+        game.winner_chain_id = winner_chain_id;
+        if let Some(opponent) = self.opponent_chain_id(&game) {
+            self.runtime
+                .send_message(opponent, CrossChainMessage::GameSync { game: game.clone() });
+        }
+        self.finish_match(game).await;
+    }
+
+    /// Concedes an active match. The host ends it right away; a guest asks the host to.
+    async fn resign(&mut self, game: Game) {
+        let opponent = self
+            .opponent_chain_id(&game)
+            .expect("Match has no opponent");
+        if self.is_host(&game) {
+            self.end_match_early(game, Some(opponent.to_string())).await;
+        } else {
+            let player_chain_id = self.runtime.chain_id();
+            self.runtime.send_message(
+                opponent,
+                CrossChainMessage::Resignation {
+                    match_id: game.match_id,
+                    player_chain_id,
+                },
+            );
+        }
+        self.state.last_notification.set(Some("You resigned".to_string()));
+    }
+
+    /// The account on `chain_id` owned by this application, which holds match stakes.
+    fn escrow_account(&mut self, chain_id: ChainId) -> Account {
+        Account::new(chain_id, self.runtime.application_id().into())
+    }
+
+    /// Moves `amount` from this chain's balance into the escrow account on `host`.
+    fn deposit_stake(&mut self, host: ChainId, amount: Amount) {
+        let escrow = self.escrow_account(host);
+        self.runtime.transfer(AccountOwner::CHAIN, escrow, amount);
+    }
+
+    /// Pays `amount` from this chain's escrow account to the chain balance of `chain_id`.
+    fn release_stake(&mut self, chain_id: ChainId, amount: Amount) {
+        let escrow = self.runtime.application_id().into();
+        self.runtime.transfer(escrow, Account::chain(chain_id), amount);
+    }
+
+    /// Pays both stakes to the winner of a finished match, or refunds each player on a draw or
+    /// abandonment. Only the host holds the escrow.
+    fn settle_stakes(&mut self, game: &Game) {
+        if game.stake == Amount::ZERO || !self.is_host(game) {
+            return;
+        }
+        let winner = game
+            .winner_chain_id
+            .as_ref()
+            .filter(|_| game.status == MatchStatus::Ended && game.players.len() == 2);
+        if let Some(winner) = winner {
+            let winner: ChainId = winner.parse().expect("Invalid winner chain ID");
+            let pot = game.stake.try_add(game.stake).expect("Stake overflow");
+            self.release_stake(winner, pot);
+            return;
+        }
+        for player in &game.players {
+            let chain_id: ChainId = player.chain_id.parse().expect("Invalid player chain ID");
+            self.release_stake(chain_id, game.stake);
+        }
+    }
+
+    /// Checks that a guest offering `stake` may join `match_id` on this chain.
+    async fn check_join(
+        &mut self,
+        match_id: &str,
+        stake: Amount,
+        room_code: Option<&str>,
+        invited: bool,
+    ) -> Result<Game, JoinError> {
+        let game = self.game(match_id).await.ok_or(JoinError::MatchNotFound)?;
+        if !self.is_host(&game) {
+            return Err(JoinError::NotHost);
+        }
+        if game.status != MatchStatus::WaitingForPlayer {
+            return Err(JoinError::NotJoinable);
+        }
+        if game.players.len() >= 2 {
+            return Err(JoinError::MatchFull);
+        }
+        if game.stake != stake {
+            return Err(JoinError::StakeMismatch);
+        }
+        if let Some(expected) = game.room_code_hash.filter(|_| !invited) {
+            if room_code.map(|code| room_code_hash(match_id, code)) != Some(expected) {
+                return Err(JoinError::WrongRoomCode);
+            }
+        }
+        Ok(game)
+    }
+
+    fn invitation_key(match_id: &str, chain_id: &str) -> String {
+        format!("{}/{}", match_id, chain_id)
+    }
+
+    /// Whether this host invited `chain_id` to `match_id` and the invitation has not expired.
+    async fn has_invitation(&mut self, match_id: &str, chain_id: &str) -> bool {
+        let now_micros = self.runtime.system_time().micros();
+        self.state
+            .sent_invitations
+            .get(&Self::invitation_key(match_id, chain_id))
+            .await
+            .expect("Failed to read invitations")
+            .is_some_and(|invitation| invitation.expires_at_micros >= now_micros)
+    }
+
+    /// Forgets every invitation sent for `match_id`, once it filled or ended.
+    async fn clear_invitations(&mut self, match_id: &str) {
+        let prefix = format!("{}/", match_id);
+        let keys = self
+            .state
+            .sent_invitations
+            .indices()
+            .await
+            .expect("Failed to read invitations");
+        for key in keys.into_iter().filter(|k| k.starts_with(&prefix)) {
+            self.state
+                .sent_invitations
+                .remove(&key)
+                .expect("Failed to remove invitation");
+        }
+    }
+
+    async fn archived_game(&self, match_id: &str) -> Option<Game> {
+        let position = self
+            .state
+            .archive_index
+            .get(match_id)
+            .await
+            .expect("Failed to read archive index")?;
+        self.state
+            .archive
+            .get(position)
+            .await
+            .expect("Failed to read match archive")
+    }
+
+    /// Starts a new match between the players of `previous`, hosted by this chain. Rematches
+    /// keep the format, rules, deadline and orchestrator but carry no stake.
+    fn start_rematch(&mut self, previous: &Game) {
+        let chain_id = self.runtime.chain_id().to_string();
+        let mut players = previous.players.clone();
+        players.sort_by_key(|p| p.chain_id != chain_id);
+        let guest_chain_id = self
+            .opponent_chain_id(previous)
+            .expect("Rematch needs an opponent");
+        let mut game = self.new_game(
+            players,
+            previous.format,
+            previous.rules.clone(),
+            Some(previous.round_timeout_micros / 1_000_000),
+        );
+        game.orchestrator_chain_id = previous.orchestrator_chain_id.clone();
+        self.save_game(&game);
+        self.reset_round_local_state(&game.match_id);
+        self.publish(MatchEvent::MatchCreated { game: game.clone() });
+        self.state
+            .last_notification
+            .set(Some("Rematch started".to_string()));
+        self.runtime
+            .send_message(guest_chain_id, CrossChainMessage::InitialStateSync { game });
+    }
+
+    /// Whether this chain hosts the rematch of `previous`.
+    fn hosts_rematch(&mut self, previous: &Game, swap_host: bool) -> bool {
+        self.is_host(previous) != swap_host
+    }
+
+    /// Withdraws a match from the lobby it is listed in, if any. Only the host lists matches.
+    fn delist(&mut self, game: &mut Game) {
+        if !self.is_host(game) {
+            return;
+        }
+        let Some(lobby) = game.lobby_chain_id.take() else {
+            return;
+        };
+        let Ok(lobby) = lobby.parse() else {
+            return;
+        };
+        self.runtime.send_message(
+            lobby,
+            CrossChainMessage::LobbyDelisted {
+                match_id: game.match_id.clone(),
+            },
+        );
+    }
+
+    async fn is_archived(&self, match_id: &str) -> bool {
+        self.state
+            .archive_index
+            .contains_key(match_id)
+            .await
+            .expect("Failed to read archive index")
+    }
+
+    async fn archive_match(&mut self, game: Game) {
+        self.remove_match(&game.match_id);
+        if self.is_archived(&game.match_id).await {
+            return;
+        }
+        let position = self.state.archive.count();
+        self.state
+            .archive_index
+            .insert(&game.match_id, position)
+            .expect("Failed to store archive index");
+        let this_chain = self.runtime.chain_id().to_string();
+        for player in game.players.iter().filter(|p| p.chain_id != this_chain) {
+            self.state
+                .archive_by_opponent
+                .get_mut_or_default(&player.chain_id)
+                .await
+                .expect("Failed to read archive index")
+                .push(position);
+        }
+        self.state.archive.push(game);
+    }
+
+    /// Sends the final result of an ended match to the orchestrator that paired it. Only the
+    /// host reports, so each match is rated once.
+    fn report_result(&mut self, game: &Game) {
+        if !self.is_host(game) {
+            return;
+        }
+        let Some(orchestrator) = game
+            .orchestrator_chain_id
+            .as_ref()
+            .and_then(|c| c.parse::<ChainId>().ok())
+        else {
+            return;
+        };
+        let Some(guest_chain_id) = game.guest_chain_id().and_then(|c| c.parse().ok()) else {
+            return;
+        };
+        let host_chain_id = self.runtime.chain_id();
+        self.runtime.send_message(
+            orchestrator,
+            CrossChainMessage::MatchResult {
+                match_id: game.match_id.clone(),
+                host_chain_id,
+                guest_chain_id,
+                winner_chain_id: game.winner_chain_id.as_ref().and_then(|c| c.parse().ok()),
+                host_score: game.host_score,
+                guest_score: game.guest_score,
+                tournament_id: game.tournament_id.clone(),
+            },
+        );
+    }
+
+    /// Applies a reported match result to both players' Elo ratings on this orchestrator.
+    async fn rate_match(
+        &mut self,
+        match_id: String,
+        host_chain_id: ChainId,
+        guest_chain_id: ChainId,
+        winner_chain_id: Option<ChainId>,
+    ) {
+        if self
+            .state
+            .rated_matches
+            .contains(&match_id)
+            .await
+            .expect("Failed to read rated matches")
+        {
+            return;
+        }
+        self.state
+            .rated_matches
+            .insert(&match_id)
+            .expect("Failed to store rated match");
+
+        let host = host_chain_id.to_string();
+        let guest = guest_chain_id.to_string();
+        let mut host_rating = self.player_rating(&host).await;
+        let mut guest_rating = self.player_rating(&guest).await;
+        let host_result = match winner_chain_id {
+            Some(winner) if winner == host_chain_id => GameResult::Win,
+            Some(_) => GameResult::Loss,
+            None => GameResult::Draw,
+        };
+        let guest_result = match host_result {
+            GameResult::Win => GameResult::Loss,
+            GameResult::Loss => GameResult::Win,
+            GameResult::Draw => GameResult::Draw,
+        };
+        let timestamp = self.runtime.system_time().micros().to_string();
+        let (host_old, guest_old) = (host_rating.rating, guest_rating.rating);
+        for (player, opponent, old_rating, opponent_old, result) in [
+            (&mut host_rating, &guest, host_old, guest_old, host_result),
+            (&mut guest_rating, &host, guest_old, host_old, guest_result),
+        ] {
+            player.rating = elo_rating(old_rating, opponent_old, result);
+            player.record(result);
+            let mut history = self
+                .state
+                .rating_history
+                .get(&player.chain_id)
+                .await
+                .expect("Failed to read rating history")
+                .unwrap_or_default();
+            history.push(RatingChange {
+                match_id: match_id.clone(),
+                opponent_chain_id: opponent.clone(),
+                result,
+                old_rating,
+                new_rating: player.rating,
+                timestamp: timestamp.clone(),
+            });
+            if history.len() > 50 {
+                let excess = history.len() - 50;
+                history.drain(0..excess);
+            }
+            self.state
+                .rating_history
+                .insert(&player.chain_id, history)
+                .expect("Failed to store rating history");
+        }
+        self.state
+            .ratings
+            .insert(&host, host_rating)
+            .expect("Failed to store rating");
+        self.state
+            .ratings
+            .insert(&guest, guest_rating)
+            .expect("Failed to store rating");
+    }
+
+    /// Pairs queued players with the same format whose ratings are close enough. Each player
+    /// accepts opponents within a rating window that widens the longer they wait, and a pair
+    /// is made if either player's window covers the gap. Earlier entries are paired first and
+    /// host the match.
+    async fn pair_queued_players(&mut self, queue: &mut Vec<MatchmakingPlayer>, now_micros: u64) {
+        let mut ratings = Vec::with_capacity(queue.len());
+        for player in queue.iter() {
+            ratings.push(self.player_rating(&player.chain_id).await.rating);
+        }
+
+        loop {
+            let mut pair = None;
+            for (i, host) in queue.iter().enumerate() {
+                let host_window =
+                    rating_window(now_micros.saturating_sub(host.enqueued_at_micros));
+                pair = queue
+                    .iter()
+                    .enumerate()
+                    .skip(i + 1)
+                    .filter(|(_, guest)| guest.format == host.format)
+                    .filter(|(j, guest)| {
+                        let guest_window =
+                            rating_window(now_micros.saturating_sub(guest.enqueued_at_micros));
+                        ratings[i].abs_diff(ratings[*j]) <= host_window.max(guest_window)
+                    })
+                    .min_by_key(|(j, _)| ratings[i].abs_diff(ratings[*j]))
+                    .map(|(j, _)| (i, j));
+                if pair.is_some() {
+                    break;
+                }
+            }
+            let Some((host_index, guest_index)) = pair else {
+                return;
+            };
+
+            let guest = queue.remove(guest_index);
+            ratings.remove(guest_index);
+            let host = queue.remove(host_index);
+            ratings.remove(host_index);
+            self.propose_match(host, guest, now_micros);
+        }
+    }
+
+    /// Asks both paired players to accept before the game is created.
+    fn propose_match(
+        &mut self,
+        host: MatchmakingPlayer,
+        guest: MatchmakingPlayer,
+        now_micros: u64,
+    ) {
+        let counter = *self.state.ready_check_counter.get();
+        self.state.ready_check_counter.set(counter + 1);
+        let check_id = format!("{}-check-{}", self.runtime.chain_id(), counter);
+        let deadline_micros = now_micros.saturating_add(READY_CHECK_WINDOW_MICROS);
+        let pending = PendingMatch {
+            check_id: check_id.clone(),
+            host,
+            guest,
+            host_accepted: false,
+            guest_accepted: false,
+            deadline_micros,
+        };
+        self.send_ready_check(&pending, &pending.host);
+        self.send_ready_check(&pending, &pending.guest);
+        self.state
+            .pending_matches
+            .insert(&check_id, pending)
+            .expect("Failed to store pending match");
+    }
+
+    /// Asks `player`, one side of `pending`, to accept the match.
+    fn send_ready_check(&mut self, pending: &PendingMatch, player: &MatchmakingPlayer) {
+        let opponent = if player.chain_id == pending.host.chain_id {
+            &pending.guest
+        } else {
+            &pending.host
+        };
+        let chain_id: ChainId = player.chain_id.parse().expect("Invalid player chain ID");
+        self.runtime.send_message(
+            chain_id,
+            CrossChainMessage::ReadyCheckRequest {
+                check_id: pending.check_id.clone(),
+                opponent_name: opponent.player_name.clone(),
+                format: pending.host.format,
+                deadline_micros: pending.deadline_micros,
+            },
+        );
+    }
+
+    /// Ends a ready check that did not complete: players who accepted go back to the front of
+    /// the queue with their original enqueue time, the others are dropped from it.
+    fn cancel_ready_check(&mut self, pending: PendingMatch, queue: &mut Vec<MatchmakingPlayer>) {
+        self.state
+            .pending_matches
+            .remove(&pending.check_id)
+            .expect("Failed to remove pending match");
+        let players = [
+            (pending.guest, pending.guest_accepted),
+            (pending.host, pending.host_accepted),
+        ];
+        for (player, accepted) in players {
+            queue.retain(|p| p.chain_id != player.chain_id);
+            let Ok(chain_id) = player.chain_id.parse::<ChainId>() else {
+                continue;
+            };
+            self.runtime.send_message(
+                chain_id,
+                CrossChainMessage::ReadyCheckCancelled {
+                    check_id: pending.check_id.clone(),
+                },
+            );
+            if accepted {
+                queue.insert(0, player);
+            }
+        }
+    }
+
+    /// Cancels every ready check whose window has passed.
+    async fn expire_ready_checks(&mut self, queue: &mut Vec<MatchmakingPlayer>, now_micros: u64) {
+        let pending = self
+            .state
+            .pending_matches
+            .index_values()
+            .await
+            .expect("Failed to read pending matches");
+        for (_, pending) in pending {
+            if pending.deadline_micros < now_micros {
+                self.cancel_ready_check(pending, queue);
+            }
+        }
+    }
+
+    /// Records a player's answer to a ready check, starting the game once both accepted.
+    async fn answer_ready_check(
+        &mut self,
+        check_id: &str,
+        player_chain_id: &str,
+        accept: bool,
+        queue: &mut Vec<MatchmakingPlayer>,
+        now_micros: u64,
+    ) {
+        let Some(mut pending) = self
+            .state
+            .pending_matches
+            .get(check_id)
+            .await
+            .expect("Failed to read pending matches")
+        else {
+            return;
+        };
+        if pending.host.chain_id == player_chain_id {
+            pending.host_accepted = accept;
+        } else if pending.guest.chain_id == player_chain_id {
+            pending.guest_accepted = accept;
+        } else {
+            return;
+        }
+        if !accept {
+            self.cancel_ready_check(pending, queue);
+        } else if pending.host_accepted && pending.guest_accepted {
+            self.state
+                .pending_matches
+                .remove(check_id)
+                .expect("Failed to remove pending match");
+            self.start_matched_game(pending.host, pending.guest, now_micros)
+                .await;
+        } else {
+            self.state
+                .pending_matches
+                .insert(check_id, pending)
+                .expect("Failed to store pending match");
+        }
+    }
+
+    async fn start_matched_game(
+        &mut self,
+        host: MatchmakingPlayer,
+        guest: MatchmakingPlayer,
+        now_micros: u64,
+    ) {
+        let waits_micros = [&host, &guest].map(|p| now_micros.saturating_sub(p.enqueued_at_micros));
+        self.state
+            .matchmaking_stats
+            .get_mut()
+            .record(now_micros, &waits_micros);
+        let format = host.format;
+        self.dispatch_match(
+            PlayerInfo {
+                chain_id: host.chain_id,
+                name: host.player_name,
+            },
+            PlayerInfo {
+                chain_id: guest.chain_id,
+                name: guest.player_name,
+            },
+            format,
+            None,
+        )
+        .await;
+    }
+
+    /// Asks the host chain to create a match against the guest, and tells the guest. The
+    /// pairing is recorded so that only a result for a match this chain dispatched is rated.
+    async fn dispatch_match(
+        &mut self,
+        host: PlayerInfo,
+        guest: PlayerInfo,
+        format: MatchFormat,
+        tournament_id: Option<String>,
+    ) {
+        let host_chain_id: ChainId = host.chain_id.parse().expect("Invalid host chain ID");
+        let guest_chain_id: ChainId = guest.chain_id.parse().expect("Invalid guest chain ID");
+        *self
+            .state
+            .dispatched_matches
+            .get_mut_or_default(&Self::dispatch_key(host_chain_id, guest_chain_id))
+            .await
+            .expect("Failed to read dispatched matches") += 1;
+        self.runtime.send_message(
+            host_chain_id,
+            CrossChainMessage::MatchmakingStart {
+                host_name: host.name,
+                guest_chain_id,
+                guest_name: guest.name,
+                format,
+                tournament_id,
+            },
+        );
+        self.runtime.send_message(
+            guest_chain_id,
+            CrossChainMessage::MatchmakingFound { host_chain_id },
+        );
+    }
+
+    async fn tournament(&self, tournament_id: &str) -> Option<Tournament> {
+        self.state
+            .tournaments
+            .get(tournament_id)
+            .await
+            .expect("Failed to read tournaments")
+    }
+
+    fn save_tournament(&mut self, tournament: &Tournament) {
+        self.state
+            .tournaments
+            .insert(&tournament.tournament_id, tournament.clone())
+            .expect("Failed to store tournament");
+    }
+
+    async fn dispatch_tournament_matches(
+        &mut self,
+        tournament: &Tournament,
+        pairings: Vec<Pairing>,
+    ) {
+        for (host, guest) in pairings {
+            self.dispatch_match(
+                host,
+                guest,
+                tournament.format,
+                Some(tournament.tournament_id.clone()),
+            )
+            .await;
+        }
+    }
+
+    fn dispatch_key(host: ChainId, guest: ChainId) -> String {
+        format!("{}/{}", host, guest)
+    }
+
+    /// Consumes one outstanding dispatch of this pairing, returning whether there was one.
+    async fn take_dispatched_match(&mut self, host: ChainId, guest: ChainId) -> bool {
+        let key = Self::dispatch_key(host, guest);
+        let outstanding = self
+            .state
+            .dispatched_matches
+            .get(&key)
+            .await
+            .expect("Failed to read dispatched matches")
+            .unwrap_or(0);
+        match outstanding {
+            0 => return false,
+            1 => self.state.dispatched_matches.remove(&key),
+            n => self.state.dispatched_matches.insert(&key, n - 1),
+        }
+        .expect("Failed to store dispatched matches");
+        true
+    }
+
+    /// Seeds the bracket by rating on this orchestrator and starts the first round.
+    async fn start_tournament(&mut self, mut tournament: Tournament) {
+        let mut seeded = Vec::with_capacity(tournament.players.len());
+        for player in tournament.players.drain(..) {
+            let rating = self.player_rating(&player.chain_id).await.rating;
+            seeded.push((rating, player));
+        }
+        seeded.sort_by_key(|(rating, _)| std::cmp::Reverse(*rating));
+        let pairings = tournament.start(seeded.into_iter().map(|(_, p)| p).collect());
+        self.dispatch_tournament_matches(&tournament, pairings)
+            .await;
+        self.save_tournament(&tournament);
+    }
+
+    /// Resolves the orchestrator a player talks to, defaulting to the first pinned one.
+    fn orchestrator(&mut self, orchestrator_chain_id: Option<String>) -> ChainId {
+        let parameters = self.runtime.application_parameters();
+        let orchestrator: ChainId = match orchestrator_chain_id {
+            Some(chain_id) => chain_id.parse().expect("Invalid orchestrator chain ID"),
+            None => *parameters
+                .orchestrator_chain_ids
+                .first()
+                .expect("No orchestrator configured"),
+        };
+        if !parameters.is_orchestrator(orchestrator) {
+            panic!("Orchestrator not allowed");
+        }
+        orchestrator
+    }
+
+    /// Expires stale queue entries and ready checks and pairs whoever now fits. Runs on every
+    /// message an orchestrator handles, so widening rating windows get re-checked even when
+    /// nobody new joins the queue; chains with nobody queued or awaiting a ready check skip it.
+    async fn maintain_queue(&mut self) {
+        let pending_count = self
+            .state
+            .pending_matches
+            .count()
+            .await
+            .expect("Failed to read pending matches");
+        if self.state.matchmaking_queue.get().is_empty() && pending_count == 0 {
+            return;
+        }
+        let mut queue = self.state.matchmaking_queue.get().clone();
+        let now_micros: u64 = self.runtime.system_time().micros();
+        self.prune_queue(&mut queue, now_micros);
+        self.expire_ready_checks(&mut queue, now_micros).await;
+        self.pair_queued_players(&mut queue, now_micros).await;
+        self.state.matchmaking_queue.set(queue);
+    }
+
+    /// Drops queue entries older than [`MATCHMAKING_ENTRY_TTL_MICROS`] and tells those players
+    /// their search expired.
+    fn prune_queue(&mut self, queue: &mut Vec<MatchmakingPlayer>, now_micros: u64) {
+        let cutoff_micros = now_micros.saturating_sub(MATCHMAKING_ENTRY_TTL_MICROS);
+        let (kept, expired): (Vec<_>, Vec<_>) = queue
+            .drain(..)
+            .partition(|p| p.enqueued_at_micros >= cutoff_micros);
+        *queue = kept;
+        let orchestrator_chain_id = self.runtime.chain_id();
+        for player in expired {
+            let Ok(player_chain_id) = player.chain_id.parse::<ChainId>() else {
+                continue;
+            };
+            self.runtime.send_message(
+                player_chain_id,
+                CrossChainMessage::MatchmakingExpired {
+                    orchestrator_chain_id,
+                },
+            );
+        }
+    }
+
+    async fn player_rating(&self, chain_id: &str) -> PlayerRating {
+        self.state
+            .ratings
+            .get(chain_id)
+            .await
+            .expect("Failed to read rating")
+            .unwrap_or_else(|| PlayerRating::new(chain_id.to_string()))
+    }
 }
 
 impl Contract for SpsContract {
     type Message = CrossChainMessage;
     type InstantiationArgument = InstantiationArgument;
     type Parameters = SpsParameters;
-    type EventValue = ();
+    type EventValue = MatchEvent;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = SpsState::load(runtime.root_view_storage_context())
@@ -77,88 +1092,153 @@ impl Contract for SpsContract {
     }
 
     async fn instantiate(&mut self, _argument: InstantiationArgument) {
-        self.state.game.set(None);
-        self.reset_round_local_state();
+        self.state.match_counter.set(0);
         self.state.last_notification.set(None);
         self.state.matchmaking_queue.set(Vec::new());
     }
 
     async fn execute_operation(&mut self, operation: Operation) -> () {
         match operation {
-            Operation::CreateMatch { host_name } => {
+            Operation::CreateMatch {
+                host_name,
+                format,
+                rules,
+                round_timeout_secs,
+                stake,
+                room_code,
+            } => {
                 let chain_id = self.runtime.chain_id().to_string();
-                let match_id = self.runtime.system_time().micros().to_string();
-                let game = Game {
-                    match_id,
-                    host_chain_id: chain_id.clone(),
-                    status: MatchStatus::WaitingForPlayer,
-                    players: vec![PlayerInfo {
+                let format =
+                    format.unwrap_or_else(|| self.runtime.application_parameters().default_format);
+                let rules = match rules {
+                    Some(rules) => rules.normalized().expect("Invalid rule set"),
+                    None => GameRules::classic(),
+                };
+                let mut game = self.new_game(
+                    vec![PlayerInfo {
                         chain_id,
                         name: host_name,
                     }],
-                    round: 1,
-                    host_score: 0,
-                    guest_score: 0,
-                    last_round: None,
-                    last_host_choice: None,
-                    last_guest_choice: None,
-                    last_outcome: None,
-                    history: Vec::new(),
-                    winner_chain_id: None,
-                };
-                self.state.game.set(Some(game));
-                self.reset_round_local_state();
+                    format,
+                    rules,
+                    round_timeout_secs,
+                );
+                game.stake = stake.unwrap_or(Amount::ZERO);
+                game.room_code_hash = room_code.map(|code| room_code_hash(&game.match_id, &code));
+                if game.stake > Amount::ZERO {
+                    let host = self.runtime.chain_id();
+                    self.deposit_stake(host, game.stake);
+                }
+                self.save_game(&game);
+                self.publish(MatchEvent::MatchCreated { game });
                 self.state.last_notification.set(None);
             }
 
             Operation::JoinMatch {
                 host_chain_id,
+                match_id,
                 player_name,
+                stake,
+                room_code,
             } => {
                 let target_chain: ChainId = host_chain_id.parse().expect("Invalid host chain ID");
+                let stake = stake.unwrap_or(Amount::ZERO);
+                if stake > Amount::ZERO {
+                    self.deposit_stake(target_chain, stake);
+                }
+                self.request_join(target_chain, match_id, player_name, stake, room_code);
+            }
+
+            Operation::SearchPlayer {
+                orchestrator_chain_id,
+                player_name,
+                format,
+            } => {
+                let orchestrator = self.orchestrator(orchestrator_chain_id);
                 let player_chain_id = self.runtime.chain_id();
+                let format =
+                    format.unwrap_or_else(|| self.runtime.application_parameters().default_format);
+                if !format.is_valid() {
+                    panic!("Invalid match format");
+                }
+                self.state
+                    .last_notification
+                    .set(Some("Matchmaking search started".to_string()));
                 self.runtime.send_message(
-                    target_chain,
-                    CrossChainMessage::JoinRequest {
+                    orchestrator,
+                    CrossChainMessage::MatchmakingEnqueue {
                         player_chain_id,
                         player_name,
+                        format,
                     },
                 );
             }
 
-            Operation::SearchPlayer {
+            Operation::CancelSearch {
                 orchestrator_chain_id,
-                player_name,
             } => {
-                let orchestrator: ChainId =
-                    orchestrator_chain_id.parse().expect("Invalid orchestrator chain ID");
+                let orchestrator = self.orchestrator(orchestrator_chain_id);
                 let player_chain_id = self.runtime.chain_id();
+                self.runtime.send_message(
+                    orchestrator,
+                    CrossChainMessage::MatchmakingDequeue { player_chain_id },
+                );
                 self.state
                     .last_notification
-                    .set(Some("Matchmaking search started".to_string()));
+                    .set(Some("Matchmaking search cancelled".to_string()));
+            }
+
+            Operation::RespondReadyCheck { check_id, accept } => {
+                let check = self
+                    .state
+                    .ready_checks
+                    .get(&check_id)
+                    .await
+                    .expect("Failed to read ready checks")
+                    .expect("Ready check not found");
+                self.state
+                    .ready_checks
+                    .remove(&check_id)
+                    .expect("Failed to remove ready check");
+                let orchestrator: ChainId = check
+                    .orchestrator_chain_id
+                    .parse()
+                    .expect("Invalid orchestrator chain ID");
+                let player_chain_id = self.runtime.chain_id();
                 self.runtime.send_message(
                     orchestrator,
-                    CrossChainMessage::MatchmakingEnqueue {
+                    CrossChainMessage::ReadyCheckResponse {
+                        check_id,
                         player_chain_id,
-                        player_name,
+                        accept,
                     },
                 );
+                let notification = if accept {
+                    "Ready check accepted, waiting for opponent"
+                } else {
+                    "Ready check declined"
+                };
+                self.state
+                    .last_notification
+                    .set(Some(notification.to_string()));
             }
 
-            Operation::PickAndReady { choice } => {
-                let game = self.state.game.get().clone().expect("Match not found");
+            Operation::PickAndReady {
+                match_id,
+                commitment,
+            } => {
+                let game = self.game(&match_id).await.expect("Match not found");
                 if !self.can_play(&game) {
                     panic!("Match not ready");
                 }
-                if self.state.my_ready.get().clone() {
+                let mut round_state = self.round_state(&match_id).await;
+                if round_state.my_ready {
                     panic!("Already ready");
                 }
-                if self.state.my_choice.get().is_some() {
-                    panic!("Choice already set");
-                }
 
-                self.state.my_choice.set(Some(choice));
-                self.state.my_ready.set(true);
+                round_state.my_commitment = Some(commitment);
+                round_state.my_ready = true;
+                self.save_round_state(&match_id, round_state);
 
                 let opponent = self.opponent_chain_id(&game).expect("Opponent not found");
                 let round = game.round;
@@ -166,219 +1246,871 @@ impl Contract for SpsContract {
                 self.runtime.send_message(
                     opponent,
                     CrossChainMessage::ReadyNotice {
+                        match_id,
                         player_chain_id,
                         round,
+                        commitment,
                     },
                 );
+            }
 
-                if self.state.opponent_ready.get().clone() {
-                    let player_chain_id = self.runtime.chain_id();
-                    self.runtime.send_message(
-                        opponent,
-                        CrossChainMessage::ChoiceReveal {
-                            player_chain_id,
-                            round,
-                            choice,
+            Operation::RevealChoice {
+                match_id,
+                choice,
+                salt,
+            } => {
+                let game = self.game(&match_id).await.expect("Match not found");
+                if !self.can_play(&game) {
+                    panic!("Match not ready");
+                }
+                let mut round_state = self.round_state(&match_id).await;
+                let commitment = round_state.my_commitment.expect("Choice not committed");
+                if !round_state.opponent_ready {
+                    panic!("Opponent has not committed yet");
+                }
+                if round_state.my_choice.is_some() {
+                    panic!("Choice already revealed");
+                }
+                if !game.rules.is_move(choice) {
+                    panic!("Invalid choice");
+                }
+                let player_chain_id = self.runtime.chain_id();
+                let expected = choice_commitment(
+                    &game.match_id,
+                    game.round,
+                    &player_chain_id.to_string(),
+                    choice,
+                    &salt,
+                );
+                if expected != commitment {
+                    panic!("Choice does not match commitment");
+                }
+
+                round_state.my_choice = Some(choice);
+                self.save_round_state(&match_id, round_state.clone());
+
+                let opponent = self.opponent_chain_id(&game).expect("Opponent not found");
+                let round = game.round;
+                self.runtime.send_message(
+                    opponent,
+                    CrossChainMessage::ChoiceReveal {
+                        match_id,
+                        player_chain_id,
+                        round,
+                        choice,
+                        salt,
+                    },
+                );
+
+                if self.is_host(&game) {
+                    self.try_resolve_round(game, &round_state).await;
+                }
+            }
+
+            Operation::ClaimTimeout {
+                match_id,
+                claim_match,
+            } => {
+                let game = self.game(&match_id).await.expect("Match not found");
+                if !self.can_play(&game) {
+                    panic!("Match not active");
+                }
+                let deadline = game.round_deadline_micros.expect("Round has no deadline");
+                if self.runtime.system_time().micros() <= deadline {
+                    panic!("Round deadline has not passed");
+                }
+                let round_state = self.round_state(&match_id).await;
+                let my_choice = round_state.my_choice;
+                let opponent_stalled = (round_state.my_ready && !round_state.opponent_ready)
+                    || (my_choice.is_some() && round_state.opponent_choice.is_none());
+                if !opponent_stalled {
+                    panic!("Opponent is not holding up the round");
+                }
+
+                if !self.is_host(&game) {
+                    let host = self
+                        .opponent_chain_id(&game)
+                        .expect("Match has no opponent");
+                    let player_chain_id = self.runtime.chain_id();
+                    self.runtime.send_message(
+                        host,
+                        CrossChainMessage::TimeoutClaim {
+                            match_id,
+                            player_chain_id,
+                            round: game.round,
+                            claim_match,
                         },
                     );
+                    self.state
+                        .last_notification
+                        .set(Some("Timeout claim sent".to_string()));
+                    return;
                 }
+
+                let forfeited_by = self.opponent_chain_id(&game).map(|c| c.to_string());
+                self.state.last_notification.set(Some(if claim_match {
+                    "Match claimed on timeout".to_string()
+                } else {
+                    "Round claimed on timeout".to_string()
+                }));
+                self.apply_round(
+                    game,
+                    my_choice,
+                    None,
+                    RoundOutcome::HostWins,
+                    forfeited_by,
+                    claim_match,
+                )
+                .await;
             }
 
-            Operation::LeaveMatch => {
-                if let Some(game) = self.state.game.get().clone() {
-                    if let Some(opponent) = self.opponent_chain_id(&game) {
-                        let player_chain_id = self.runtime.chain_id();
-                        self.runtime.send_message(
-                            opponent,
-                            CrossChainMessage::LeaveNotice {
-                                player_chain_id,
-                            },
-                        );
+            Operation::LeaveMatch { match_id } => {
+                if let Some(mut game) = self.game(&match_id).await {
+                    // Leaving a match in progress concedes it, so it still gets a result. Any
+                    // other live match is still waiting for a guest and is simply abandoned.
+                    if self.can_play(&game) {
+                        self.resign(game).await;
+                        return;
                     }
+                    game.status = MatchStatus::Abandoned;
+                    game.round_deadline_micros = None;
+                    self.finish_match(game).await;
                 }
-                self.state.game.set(None);
-                self.reset_round_local_state();
                 self.state.last_notification.set(None);
             }
+
+            Operation::Resign { match_id } => {
+                let game = self.game(&match_id).await.expect("Match not found");
+                if !self.can_play(&game) {
+                    panic!("Match is not in progress");
+                }
+                self.resign(game).await;
+            }
+
+            Operation::OfferDraw { match_id } => {
+                let game = self.game(&match_id).await.expect("Match not found");
+                if !self.can_play(&game) {
+                    panic!("Match is not in progress");
+                }
+                let opponent = self
+                    .opponent_chain_id(&game)
+                    .expect("Match has no opponent");
+                self.state
+                    .offered_draws
+                    .insert(&match_id)
+                    .expect("Failed to store draw offer");
+                let player_chain_id = self.runtime.chain_id();
+                self.runtime.send_message(
+                    opponent,
+                    CrossChainMessage::DrawOffer {
+                        match_id,
+                        player_chain_id,
+                        round: game.round,
+                    },
+                );
+                self.state.last_notification.set(Some("Draw offered".to_string()));
+            }
+
+            Operation::AcceptDraw { match_id } => {
+                let game = self.game(&match_id).await.expect("Match not found");
+                if !self.can_play(&game) {
+                    panic!("Match is not in progress");
+                }
+                let offered = self
+                    .state
+                    .draw_offers
+                    .contains(&match_id)
+                    .await
+                    .expect("Failed to read draw offers");
+                if !offered {
+                    panic!("No draw offered");
+                }
+                if self.is_host(&game) {
+                    self.end_match_early(game, None).await;
+                } else {
+                    let host = self
+                        .opponent_chain_id(&game)
+                        .expect("Match has no opponent");
+                    let player_chain_id = self.runtime.chain_id();
+                    self.runtime.send_message(
+                        host,
+                        CrossChainMessage::DrawAccepted {
+                            match_id,
+                            player_chain_id,
+                        },
+                    );
+                }
+                self.state.last_notification.set(Some("Draw accepted".to_string()));
+            }
+
+            Operation::Spectate { host_chain_id } => {
+                let host: ChainId = host_chain_id.parse().expect("Invalid host chain ID");
+                let application_id = self.runtime.application_id().forget_abi();
+                self.runtime.subscribe_to_events(
+                    host,
+                    application_id,
+                    StreamName::from(MATCH_STREAM_NAME),
+                );
+                self.state
+                    .spectating
+                    .insert(&host_chain_id)
+                    .expect("Failed to store spectated host");
+            }
+
+            Operation::CreateTournament {
+                name,
+                kind,
+                bracket_size,
+                format,
+            } => {
+                let this_chain = self.runtime.chain_id();
+                if !self.runtime.application_parameters().is_orchestrator(this_chain) {
+                    panic!("Only an orchestrator can run tournaments");
+                }
+                if !(2..=MAX_BRACKET_SIZE).contains(&bracket_size) {
+                    panic!("Bracket size must be between 2 and {}", MAX_BRACKET_SIZE);
+                }
+                let format =
+                    format.unwrap_or_else(|| self.runtime.application_parameters().default_format);
+                if !format.is_valid() {
+                    panic!("Invalid match format");
+                }
+                let counter = *self.state.tournament_counter.get() + 1;
+                self.state.tournament_counter.set(counter);
+                let tournament_id = format!("{}-t{}", this_chain, counter);
+                let kind = kind.unwrap_or(TournamentKind::SingleElimination);
+                let tournament = Tournament::new(tournament_id, name, kind, format, bracket_size);
+                self.save_tournament(&tournament);
+                self.state.last_notification.set(Some(format!(
+                    "Tournament {} created",
+                    tournament.tournament_id
+                )));
+            }
+
+            Operation::StartTournament { tournament_id } => {
+                let tournament = self
+                    .tournament(&tournament_id)
+                    .await
+                    .expect("Tournament not found");
+                if tournament.status != TournamentStatus::Registration {
+                    panic!("Tournament already started");
+                }
+                if tournament.players.len() < 2 {
+                    panic!("Not enough players registered");
+                }
+                self.start_tournament(tournament).await;
+            }
+
+            Operation::PlayFixtures {
+                tournament_id,
+                round,
+            } => {
+                let mut tournament = self
+                    .tournament(&tournament_id)
+                    .await
+                    .expect("Tournament not found");
+                if tournament.kind != TournamentKind::RoundRobin {
+                    panic!("Only league fixtures are played on demand");
+                }
+                let pairings = tournament.play_fixtures(round);
+                if pairings.is_empty() {
+                    panic!("No fixtures left to play in this round");
+                }
+                self.dispatch_tournament_matches(&tournament, pairings).await;
+                self.save_tournament(&tournament);
+            }
+
+            Operation::RegisterForTournament {
+                orchestrator_chain_id,
+                tournament_id,
+                player_name,
+            } => {
+                let orchestrator = self.orchestrator(orchestrator_chain_id);
+                let player_chain_id = self.runtime.chain_id();
+                self.runtime.send_message(
+                    orchestrator,
+                    CrossChainMessage::TournamentRegister {
+                        tournament_id,
+                        player_chain_id,
+                        player_name,
+                    },
+                );
+                self.state
+                    .last_notification
+                    .set(Some("Tournament registration sent".to_string()));
+            }
+
+            Operation::PublishMatch {
+                match_id,
+                orchestrator_chain_id,
+                name,
+                description,
+            } => {
+                let mut game = self.game(&match_id).await.expect("Match not found");
+                if !self.is_host(&game) || game.status != MatchStatus::WaitingForPlayer {
+                    panic!("Only a host waiting for a guest can publish a match");
+                }
+                let orchestrator = self.orchestrator(orchestrator_chain_id);
+                if game.lobby_chain_id.is_some() {
+                    panic!("Match already published");
+                }
+                let listing = LobbyListing {
+                    match_id,
+                    host_chain_id: game.host_chain_id.clone(),
+                    name,
+                    description,
+                    format: game.format,
+                    rules: game.rules.kind,
+                    stake: game.stake,
+                    private: game.room_code_hash.is_some(),
+                    listed_at_micros: self.runtime.system_time().micros(),
+                };
+                game.lobby_chain_id = Some(orchestrator.to_string());
+                self.save_game(&game);
+                self.runtime
+                    .send_message(orchestrator, CrossChainMessage::LobbyListed { listing });
+                self.state
+                    .last_notification
+                    .set(Some("Match published to the lobby".to_string()));
+            }
+
+            Operation::StopSpectating { host_chain_id } => {
+                let host: ChainId = host_chain_id.parse().expect("Invalid host chain ID");
+                let application_id = self.runtime.application_id().forget_abi();
+                self.runtime.unsubscribe_from_events(
+                    host,
+                    application_id,
+                    StreamName::from(MATCH_STREAM_NAME),
+                );
+                self.state
+                    .spectating
+                    .remove(&host_chain_id)
+                    .expect("Failed to remove spectated host");
+                let followed = self
+                    .state
+                    .spectated
+                    .index_values()
+                    .await
+                    .expect("Failed to read spectated matches");
+                for (match_id, game) in followed {
+                    if game.host_chain_id == host_chain_id {
+                        self.state
+                            .spectated
+                            .remove(&match_id)
+                            .expect("Failed to remove spectated match");
+                    }
+                }
+            }
+
+            Operation::InvitePlayer {
+                match_id,
+                chain_id,
+                expires_in_secs,
+            } => {
+                let game = self.game(&match_id).await.expect("Match not found");
+                if !self.is_host(&game) || game.status != MatchStatus::WaitingForPlayer {
+                    panic!("Only a host waiting for a guest can invite");
+                }
+                let invitee: ChainId = chain_id.parse().expect("Invalid invitee chain ID");
+                let ttl_secs = expires_in_secs.unwrap_or(DEFAULT_INVITATION_TTL_SECS);
+                let expires_at_micros = self
+                    .runtime
+                    .system_time()
+                    .micros()
+                    .saturating_add(ttl_secs.saturating_mul(1_000_000));
+                let invitation = Invitation {
+                    match_id: match_id.clone(),
+                    host_chain_id: game.host_chain_id.clone(),
+                    host_name: game.players[0].name.clone(),
+                    invitee_chain_id: chain_id.clone(),
+                    format: game.format,
+                    rules: game.rules.kind,
+                    stake: game.stake,
+                    expires_at_micros,
+                };
+                self.state
+                    .sent_invitations
+                    .insert(&Self::invitation_key(&match_id, &chain_id), invitation.clone())
+                    .expect("Failed to store invitation");
+                self.runtime
+                    .send_message(invitee, CrossChainMessage::MatchInvite { invitation });
+                self.state
+                    .last_notification
+                    .set(Some(format!("Invited {}", chain_id)));
+            }
+
+            Operation::AcceptInvite {
+                match_id,
+                player_name,
+            } => {
+                let invitation = self
+                    .state
+                    .invitations
+                    .get(&match_id)
+                    .await
+                    .expect("Failed to read invitations")
+                    .expect("Invitation not found");
+                self.state
+                    .invitations
+                    .remove(&match_id)
+                    .expect("Failed to remove invitation");
+                if invitation.expires_at_micros < self.runtime.system_time().micros() {
+                    panic!("Invitation expired");
+                }
+                let host: ChainId = invitation
+                    .host_chain_id
+                    .parse()
+                    .expect("Invalid host chain ID");
+                if invitation.stake > Amount::ZERO {
+                    self.deposit_stake(host, invitation.stake);
+                }
+                self.request_join(host, match_id, player_name, invitation.stake, None);
+                self.state
+                    .last_notification
+                    .set(Some("Invitation accepted".to_string()));
+            }
+
+            Operation::DeclineInvite { match_id } => {
+                let invitation = self
+                    .state
+                    .invitations
+                    .get(&match_id)
+                    .await
+                    .expect("Failed to read invitations")
+                    .expect("Invitation not found");
+                self.state
+                    .invitations
+                    .remove(&match_id)
+                    .expect("Failed to remove invitation");
+                let host: ChainId = invitation
+                    .host_chain_id
+                    .parse()
+                    .expect("Invalid host chain ID");
+                let player_chain_id = self.runtime.chain_id();
+                self.runtime.send_message(
+                    host,
+                    CrossChainMessage::InviteDeclined {
+                        match_id,
+                        player_chain_id,
+                    },
+                );
+                self.state
+                    .last_notification
+                    .set(Some("Invitation declined".to_string()));
+            }
+
+            Operation::RequestRematch {
+                match_id,
+                swap_host,
+            } => {
+                let game = self
+                    .archived_game(&match_id)
+                    .await
+                    .expect("Finished match not found");
+                if game.status != MatchStatus::Ended {
+                    panic!("Match did not end");
+                }
+                let opponent = self
+                    .opponent_chain_id(&game)
+                    .expect("Match has no opponent");
+                let player_chain_id = self.runtime.chain_id();
+                self.runtime.send_message(
+                    opponent,
+                    CrossChainMessage::RematchRequest {
+                        match_id,
+                        player_chain_id,
+                        swap_host,
+                    },
+                );
+                self.state
+                    .last_notification
+                    .set(Some("Rematch requested".to_string()));
+            }
+
+            Operation::AcceptRematch { match_id } => {
+                let request = self
+                    .state
+                    .rematch_requests
+                    .get(&match_id)
+                    .await
+                    .expect("Failed to read rematch requests")
+                    .expect("No rematch requested");
+                self.state
+                    .rematch_requests
+                    .remove(&match_id)
+                    .expect("Failed to remove rematch request");
+                let game = self
+                    .archived_game(&match_id)
+                    .await
+                    .expect("Finished match not found");
+                if self.hosts_rematch(&game, request.swap_host) {
+                    self.start_rematch(&game);
+                } else {
+                    let requester: ChainId = request
+                        .requester_chain_id
+                        .parse()
+                        .expect("Invalid requester chain ID");
+                    let player_chain_id = self.runtime.chain_id();
+                    self.runtime.send_message(
+                        requester,
+                        CrossChainMessage::RematchAccepted {
+                            match_id,
+                            player_chain_id,
+                            swap_host: request.swap_host,
+                        },
+                    );
+                    self.state
+                        .last_notification
+                        .set(Some("Rematch accepted".to_string()));
+                }
+            }
         }
     }
 
     async fn execute_message(&mut self, message: Self::Message) {
+        let this_chain = self.runtime.chain_id();
+        if self.runtime.application_parameters().is_orchestrator(this_chain) {
+            self.maintain_queue().await;
+        }
         match message {
             CrossChainMessage::JoinRequest {
+                match_id,
                 player_chain_id,
                 player_name,
+                stake,
+                room_code,
             } => {
-                let mut game = self.state.game.get().clone().expect("Match not found");
-                if !self.is_host(&game) {
-                    panic!("Only host can accept joins");
-                }
-                if game.status != MatchStatus::WaitingForPlayer {
-                    panic!("Match not joinable");
-                }
-                if game.players.len() >= 2 {
-                    panic!("Match full");
+                if !self.sent_by(player_chain_id) {
+                    return;
                 }
+                let invited = self
+                    .has_invitation(&match_id, &player_chain_id.to_string())
+                    .await;
+                let joined = self
+                    .check_join(&match_id, stake, room_code.as_deref(), invited)
+                    .await;
+                let mut game = match joined {
+                    Ok(game) => game,
+                    Err(reason) => {
+                        // The guest's stake already reached our escrow, so hand it back.
+                        if stake > Amount::ZERO {
+                            self.release_stake(player_chain_id, stake);
+                        }
+                        self.runtime.send_message(
+                            player_chain_id,
+                            CrossChainMessage::JoinRejected { match_id, reason },
+                        );
+                        return;
+                    }
+                };
 
                 game.players.push(PlayerInfo {
                     chain_id: player_chain_id.to_string(),
                     name: player_name,
                 });
                 game.status = MatchStatus::Active;
-                self.state.game.set(Some(game.clone()));
-                self.reset_round_local_state();
+                self.delist(&mut game);
+                self.clear_invitations(&match_id).await;
+                self.start_round_clock(&mut game);
+                self.save_game(&game);
+                self.reset_round_local_state(&match_id);
+                self.publish(MatchEvent::PlayerJoined { game: game.clone() });
                 self.state.last_notification.set(Some("Player joined".to_string()));
                 self.runtime.send_message(player_chain_id, CrossChainMessage::InitialStateSync { game });
             }
 
+            CrossChainMessage::JoinRejected { match_id, reason } => {
+                let Some(host_chain_id) = self
+                    .state
+                    .pending_joins
+                    .get(&match_id)
+                    .await
+                    .expect("Failed to read pending joins")
+                else {
+                    return;
+                };
+                let origin = self.runtime.message_origin_chain_id().map(|c| c.to_string());
+                if origin.as_ref() != Some(&host_chain_id) {
+                    return;
+                }
+                self.state
+                    .pending_joins
+                    .remove(&match_id)
+                    .expect("Failed to clear pending join");
+                self.state
+                    .last_notification
+                    .set(Some(format!("Join rejected: {}", reason)));
+                self.state.last_join_rejection.set(Some(JoinRejection {
+                    match_id,
+                    host_chain_id,
+                    reason,
+                }));
+            }
+
             CrossChainMessage::InitialStateSync { game } => {
-                self.state.game.set(Some(game));
-                self.reset_round_local_state();
+                if !self.sent_by_host(&game) {
+                    return;
+                }
+                self.state.last_join_rejection.set(None);
+                self.state
+                    .pending_joins
+                    .remove(&game.match_id)
+                    .expect("Failed to clear pending join");
+                self.save_game(&game);
+                self.reset_round_local_state(&game.match_id);
                 self.state.last_notification.set(Some("Match ready".to_string()));
             }
 
             CrossChainMessage::GameSync { game } => {
-                self.state.game.set(Some(game));
-                self.reset_round_local_state();
+                if !self.sent_by_host(&game) {
+                    return;
+                }
+                if game.history.last().is_some_and(|r| r.forfeited_by.is_some()) {
+                    self.state
+                        .last_notification
+                        .set(Some("Round forfeited".to_string()));
+                }
+                if self.is_archived(&game.match_id).await {
+                    return;
+                }
+                if game.status == MatchStatus::Ended {
+                    self.finish_match(game).await;
+                } else {
+                    self.save_game(&game);
+                    self.reset_round_local_state(&game.match_id);
+                    self.publish(MatchEvent::RoundResolved { game });
+                }
             }
 
             CrossChainMessage::ReadyNotice {
-                player_chain_id: _,
+                match_id,
+                player_chain_id,
                 round,
+                commitment,
             } => {
-                let game = self.state.game.get().clone().expect("Match not found");
-                if !self.can_play(&game) {
+                let Some(game) = self.game(&match_id).await else {
+                    return;
+                };
+                if !self.sent_by_opponent(&game, player_chain_id) || !self.can_play(&game) {
                     return;
                 }
                 if game.round != round {
                     return;
                 }
-                self.state.opponent_ready.set(true);
-
-                if self.state.my_ready.get().clone() {
-                    if let Some(choice) = self.state.my_choice.get().clone() {
-                        if let Some(opponent) = self.opponent_chain_id(&game) {
-                            let player_chain_id = self.runtime.chain_id();
-                            self.runtime.send_message(
-                                opponent,
-                                CrossChainMessage::ChoiceReveal {
-                                    player_chain_id,
-                                    round,
-                                    choice,
-                                },
-                            );
-                        }
-                    }
+                let mut round_state = self.round_state(&match_id).await;
+                if round_state.opponent_commitment.is_some() {
+                    return;
                 }
+                round_state.opponent_commitment = Some(commitment);
+                round_state.opponent_ready = true;
+                self.save_round_state(&match_id, round_state);
             }
 
             CrossChainMessage::ChoiceReveal {
-                player_chain_id: _,
+                match_id,
+                player_chain_id,
                 round,
                 choice,
+                salt,
             } => {
-                let mut game = self.state.game.get().clone().expect("Match not found");
-                if !self.can_play(&game) {
+                let Some(game) = self.game(&match_id).await else {
+                    return;
+                };
+                if !self.sent_by_opponent(&game, player_chain_id) || !self.can_play(&game) {
                     return;
                 }
                 if game.round != round {
                     return;
                 }
-                if self.state.opponent_choice.get().is_some() {
+                let mut round_state = self.round_state(&match_id).await;
+                if round_state.opponent_choice.is_some() {
                     return;
                 }
-                self.state.opponent_choice.set(Some(choice));
-
-                if !self.is_host(&game) {
+                let Some(commitment) = round_state.opponent_commitment else {
                     return;
-                }
-                let my_choice = match self.state.my_choice.get().clone() {
-                    Some(c) => c,
-                    None => return,
                 };
-                let opponent_choice = match self.state.opponent_choice.get().clone() {
-                    Some(c) => c,
-                    None => return,
+                let Some(opponent) = self.opponent_chain_id(&game) else {
+                    return;
                 };
-
-                let (host_choice, guest_choice) =
-                    self.host_and_guest_choices(&game, my_choice, opponent_choice);
-
-                let outcome = round_outcome(host_choice, guest_choice);
-
-                game.last_round = Some(game.round);
-                game.last_host_choice = Some(host_choice);
-                game.last_guest_choice = Some(guest_choice);
-                game.last_outcome = Some(outcome);
-
-                match outcome {
-                    stone_paper_scissors::RoundOutcome::HostWins => game.host_score = game.host_score.saturating_add(1),
-                    stone_paper_scissors::RoundOutcome::GuestWins => game.guest_score = game.guest_score.saturating_add(1),
-                    stone_paper_scissors::RoundOutcome::Draw => {}
+                let expected =
+                    choice_commitment(&game.match_id, round, &opponent.to_string(), choice, &salt);
+                if expected != commitment || !game.rules.is_move(choice) {
+                    round_state.opponent_forfeit = true;
+                    self.state.last_notification.set(Some(
+                        "Opponent reveal did not match their commitment".to_string(),
+                    ));
                 }
+                round_state.opponent_choice = Some(choice);
+                self.save_round_state(&match_id, round_state.clone());
 
-                game.history.push(RoundRecord {
-                    round,
-                    host_choice,
-                    guest_choice,
-                    outcome,
-                    host_score: game.host_score,
-                    guest_score: game.guest_score,
-                    timestamp: self.runtime.system_time().micros().to_string(),
-                });
-                if game.history.len() > 50 {
-                    let excess = game.history.len() - 50;
-                    game.history.drain(0..excess);
+                if self.is_host(&game) {
+                    self.try_resolve_round(game, &round_state).await;
                 }
+            }
 
-                if game.host_score >= 3 || game.guest_score >= 3 {
-                    game.status = MatchStatus::Ended;
-                    let winner_chain_id = if game.host_score >= 3 {
-                        game.host_chain_id.clone()
-                    } else {
-                        game.players
-                            .iter()
-                            .find(|p| p.chain_id != game.host_chain_id)
-                            .map(|p| p.chain_id.clone())
-                            .unwrap_or_default()
-                    };
-                    game.winner_chain_id = Some(winner_chain_id);
-                } else {
-                    game.round = game.round.saturating_add(1);
+            CrossChainMessage::TimeoutClaim {
+                match_id,
+                player_chain_id,
+                round,
+                claim_match,
+            } => {
+                let Some(game) = self.game(&match_id).await else {
+                    return;
+                };
+                if !self.sent_by_opponent(&game, player_chain_id)
+                    || !self.is_host(&game)
+                    || !self.can_play(&game)
+                    || game.round != round
+                {
+                    return;
                 }
-
-                self.state.game.set(Some(game.clone()));
-                self.reset_round_local_state();
-
-                if let Some(opponent) = self.opponent_chain_id(&game) {
-                    self.runtime.send_message(opponent, CrossChainMessage::GameSync { game });
+                let now_micros: u64 = self.runtime.system_time().micros();
+                if game.round_deadline_micros.is_none_or(|d| now_micros <= d) {
+                    return;
+                }
+                let round_state = self.round_state(&match_id).await;
+                let host_stalled = (round_state.opponent_ready && !round_state.my_ready)
+                    || (round_state.opponent_choice.is_some() && round_state.my_choice.is_none());
+                if !host_stalled {
+                    return;
                 }
+                let forfeited_by = self.runtime.chain_id().to_string();
+                self.state.last_notification.set(Some(if claim_match {
+                    "Opponent claimed the match on timeout".to_string()
+                } else {
+                    "Opponent claimed the round on timeout".to_string()
+                }));
+                self.apply_round(
+                    game,
+                    None,
+                    round_state.opponent_choice,
+                    RoundOutcome::GuestWins,
+                    Some(forfeited_by),
+                    claim_match,
+                )
+                .await;
             }
 
-            CrossChainMessage::LeaveNotice { player_chain_id: _ } => {
-                self.state.game.set(None);
-                self.reset_round_local_state();
-                self.state.last_notification.set(Some("Opponent left".to_string()));
+            CrossChainMessage::Resignation {
+                match_id,
+                player_chain_id,
+            } => {
+                let Some(game) = self.game(&match_id).await else {
+                    return;
+                };
+                if !self.sent_by_opponent(&game, player_chain_id)
+                    || !self.is_host(&game)
+                    || !self.can_play(&game)
+                {
+                    return;
+                }
+                let winner = self.runtime.chain_id().to_string();
+                self.end_match_early(game, Some(winner)).await;
+                self.state
+                    .last_notification
+                    .set(Some("Opponent resigned".to_string()));
             }
 
-            CrossChainMessage::MatchmakingEnqueue {
+            CrossChainMessage::DrawOffer {
+                match_id,
                 player_chain_id,
-                player_name,
+                round,
             } => {
-                let mut queue = self.state.matchmaking_queue.get().clone();
-                let now_micros: u64 = self.runtime.system_time().micros();
-                let cutoff_micros: u64 = now_micros.saturating_sub(5 * 60 * 1_000_000);
-                queue.retain(|p| p.enqueued_at_micros >= cutoff_micros);
-
-                let player_chain_str = player_chain_id.to_string();
+                let Some(game) = self.game(&match_id).await else {
+                    return;
+                };
+                if !self.sent_by_opponent(&game, player_chain_id)
+                    || !self.can_play(&game)
+                    || game.round != round
+                {
+                    return;
+                }
+                self.state
+                    .draw_offers
+                    .insert(&match_id)
+                    .expect("Failed to store draw offer");
+                self.state
+                    .last_notification
+                    .set(Some("Opponent offered a draw".to_string()));
+            }
+
+            CrossChainMessage::DrawAccepted {
+                match_id,
+                player_chain_id,
+            } => {
+                let Some(game) = self.game(&match_id).await else {
+                    return;
+                };
+                if !self.sent_by_opponent(&game, player_chain_id)
+                    || !self.is_host(&game)
+                    || !self.can_play(&game)
+                {
+                    return;
+                }
+                let offered = self
+                    .state
+                    .offered_draws
+                    .contains(&match_id)
+                    .await
+                    .expect("Failed to read draw offers");
+                if !offered {
+                    return;
+                }
+                self.end_match_early(game, None).await;
+                self.state
+                    .last_notification
+                    .set(Some("Opponent accepted the draw".to_string()));
+            }
+
+            CrossChainMessage::MatchmakingEnqueue {
+                player_chain_id,
+                player_name,
+                format,
+            } => {
+                let this_chain = self.runtime.chain_id();
+                if !self.sent_by(player_chain_id)
+                    || !self.runtime.application_parameters().is_orchestrator(this_chain)
+                {
+                    return;
+                }
+                let mut queue = self.state.matchmaking_queue.get().clone();
+                let now_micros: u64 = self.runtime.system_time().micros();
+
+                let player_chain_str = player_chain_id.to_string();
+                // A player already facing a ready check is not queued a second time; they are
+                // sent the check again instead.
+                let pending = self
+                    .state
+                    .pending_matches
+                    .index_values()
+                    .await
+                    .expect("Failed to read pending matches")
+                    .into_iter()
+                    .map(|(_, pending)| pending)
+                    .find(|pending| {
+                        pending.host.chain_id == player_chain_str
+                            || pending.guest.chain_id == player_chain_str
+                    });
+                if let Some(pending) = pending {
+                    let player = if pending.host.chain_id == player_chain_str {
+                        &pending.host
+                    } else {
+                        &pending.guest
+                    };
+                    self.send_ready_check(&pending, player);
+                    return;
+                }
+                // Searching again keeps the original place in the queue, and the wider rating
+                // window that comes with it.
                 if let Some(existing) = queue.iter_mut().find(|p| p.chain_id == player_chain_str) {
                     existing.player_name = player_name.clone();
-                    existing.enqueued_at_micros = now_micros;
+                    existing.format = format;
                 } else {
-                    queue.push(stone_paper_scissors::MatchmakingPlayer {
+                    queue.push(MatchmakingPlayer {
                         chain_id: player_chain_str,
                         player_name: player_name.clone(),
                         enqueued_at_micros: now_micros,
+                        format,
                     });
                 }
                 self.state.matchmaking_queue.set(queue.clone());
@@ -391,34 +2123,168 @@ impl Contract for SpsContract {
                     },
                 );
 
-                if queue.len() < 2 {
+                self.pair_queued_players(&mut queue, now_micros).await;
+                self.state.matchmaking_queue.set(queue);
+            }
+
+            CrossChainMessage::MatchmakingDequeue { player_chain_id } => {
+                if !self.sent_by(player_chain_id) {
                     return;
                 }
+                let mut queue = self.state.matchmaking_queue.get().clone();
+                let now_micros: u64 = self.runtime.system_time().micros();
+                let player_chain_str = player_chain_id.to_string();
+                queue.retain(|p| p.chain_id != player_chain_str);
+                // Leaving the queue while a ready check is open declines it.
+                let pending = self
+                    .state
+                    .pending_matches
+                    .index_values()
+                    .await
+                    .expect("Failed to read pending matches");
+                for (check_id, pending) in pending {
+                    if pending.host.chain_id == player_chain_str
+                        || pending.guest.chain_id == player_chain_str
+                    {
+                        self.answer_ready_check(
+                            &check_id,
+                            &player_chain_str,
+                            false,
+                            &mut queue,
+                            now_micros,
+                        )
+                        .await;
+                    }
+                }
+                self.pair_queued_players(&mut queue, now_micros).await;
+                self.state.matchmaking_queue.set(queue);
+            }
+
+            CrossChainMessage::ReadyCheckRequest {
+                check_id,
+                opponent_name,
+                format,
+                deadline_micros,
+            } => {
+                if !self.sent_by_orchestrator() {
+                    return;
+                }
+                let orchestrator_chain_id = self
+                    .runtime
+                    .message_origin_chain_id()
+                    .expect("Ready check without origin")
+                    .to_string();
+                self.state
+                    .ready_checks
+                    .insert(
+                        &check_id,
+                        ReadyCheck {
+                            check_id: check_id.clone(),
+                            orchestrator_chain_id,
+                            opponent_name: opponent_name.clone(),
+                            format,
+                            deadline_micros,
+                        },
+                    )
+                    .expect("Failed to store ready check");
+                self.state.last_notification.set(Some(format!(
+                    "Opponent found: {}. Accept the ready check to start",
+                    opponent_name
+                )));
+            }
 
-                let host = queue.remove(0);
-                let guest = queue.remove(0);
+            CrossChainMessage::ReadyCheckResponse {
+                check_id,
+                player_chain_id,
+                accept,
+            } => {
+                if !self.sent_by(player_chain_id) {
+                    return;
+                }
+                let mut queue = self.state.matchmaking_queue.get().clone();
+                let now_micros: u64 = self.runtime.system_time().micros();
+                let player_chain_str = player_chain_id.to_string();
+                self.answer_ready_check(
+                    &check_id,
+                    &player_chain_str,
+                    accept,
+                    &mut queue,
+                    now_micros,
+                )
+                .await;
+                self.pair_queued_players(&mut queue, now_micros).await;
                 self.state.matchmaking_queue.set(queue);
+            }
 
-                let host_chain_id: ChainId = host.chain_id.parse().expect("Invalid host chain ID");
-                let guest_chain_id: ChainId =
-                    guest.chain_id.parse().expect("Invalid guest chain ID");
-                self.runtime.send_message(
-                    host_chain_id,
-                    CrossChainMessage::MatchmakingStart {
-                        host_name: host.player_name,
-                        guest_chain_id,
-                        guest_name: guest.player_name,
-                    },
-                );
-                self.runtime.send_message(
-                    guest_chain_id,
-                    CrossChainMessage::MatchmakingFound { host_chain_id },
-                );
+            CrossChainMessage::LobbyListed { listing } => {
+                let this_chain = self.runtime.chain_id();
+                let Ok(host) = listing.host_chain_id.parse() else {
+                    return;
+                };
+                if !self.sent_by(host)
+                    || !self.runtime.application_parameters().is_orchestrator(this_chain)
+                {
+                    return;
+                }
+                self.state
+                    .lobby
+                    .insert(&listing.match_id.clone(), listing)
+                    .expect("Failed to store lobby listing");
+            }
+
+            CrossChainMessage::LobbyDelisted { match_id } => {
+                let Some(listing) = self
+                    .state
+                    .lobby
+                    .get(&match_id)
+                    .await
+                    .expect("Failed to read lobby")
+                else {
+                    return;
+                };
+                let Ok(host) = listing.host_chain_id.parse() else {
+                    return;
+                };
+                if !self.sent_by(host) {
+                    return;
+                }
+                self.state
+                    .lobby
+                    .remove(&match_id)
+                    .expect("Failed to remove lobby listing");
+            }
+
+            CrossChainMessage::ReadyCheckCancelled { check_id } => {
+                if !self.sent_by_orchestrator() {
+                    return;
+                }
+                self.state
+                    .ready_checks
+                    .remove(&check_id)
+                    .expect("Failed to remove ready check");
+                self.state
+                    .last_notification
+                    .set(Some("Ready check cancelled".to_string()));
+            }
+
+            CrossChainMessage::MatchmakingExpired {
+                orchestrator_chain_id,
+            } => {
+                if !self.sent_by(orchestrator_chain_id) || !self.sent_by_orchestrator() {
+                    return;
+                }
+                self.state.last_notification.set(Some(format!(
+                    "Matchmaking search on {} expired",
+                    orchestrator_chain_id
+                )));
             }
 
             CrossChainMessage::MatchmakingEnqueued {
                 orchestrator_chain_id,
             } => {
+                if !self.sent_by(orchestrator_chain_id) || !self.sent_by_orchestrator() {
+                    return;
+                }
                 self.state.last_notification.set(Some(format!(
                     "Enqueued on {}",
                     orchestrator_chain_id
@@ -429,22 +2295,18 @@ impl Contract for SpsContract {
                 host_name,
                 guest_chain_id,
                 guest_name,
+                format,
+                tournament_id,
             } => {
-                if let Some(game) = self.state.game.get().clone() {
-                    if game.status == MatchStatus::Active {
-                        return;
-                    }
+                if !self.sent_by_orchestrator() {
+                    return;
                 }
-
                 let chain_id = self.runtime.chain_id().to_string();
-                let match_id = self.runtime.system_time().micros().to_string();
-                let game = Game {
-                    match_id,
-                    host_chain_id: chain_id.clone(),
-                    status: MatchStatus::Active,
-                    players: vec![
+                let orchestrator = self.runtime.message_origin_chain_id();
+                let mut game = self.new_game(
+                    vec![
                         PlayerInfo {
-                            chain_id: chain_id.clone(),
+                            chain_id,
                             name: host_name,
                         },
                         PlayerInfo {
@@ -452,19 +2314,15 @@ impl Contract for SpsContract {
                             name: guest_name,
                         },
                     ],
-                    round: 1,
-                    host_score: 0,
-                    guest_score: 0,
-                    last_round: None,
-                    last_host_choice: None,
-                    last_guest_choice: None,
-                    last_outcome: None,
-                    history: Vec::new(),
-                    winner_chain_id: None,
-                };
+                    format,
+                    GameRules::classic(),
+                    None,
+                );
+                game.orchestrator_chain_id = orchestrator.map(|c| c.to_string());
+                game.tournament_id = tournament_id;
 
-                self.state.game.set(Some(game.clone()));
-                self.reset_round_local_state();
+                self.save_game(&game);
+                self.publish(MatchEvent::MatchCreated { game: game.clone() });
                 self.state
                     .last_notification
                     .set(Some("Match found (host)".to_string()));
@@ -473,21 +2331,828 @@ impl Contract for SpsContract {
             }
 
             CrossChainMessage::MatchmakingFound { host_chain_id } => {
+                if !self.sent_by_orchestrator() {
+                    return;
+                }
                 self.state.last_notification.set(Some(format!(
                     "Match found. Host: {}",
                     host_chain_id
                 )));
             }
+
+            CrossChainMessage::MatchResult {
+                match_id,
+                host_chain_id,
+                guest_chain_id,
+                winner_chain_id,
+                host_score,
+                guest_score,
+                tournament_id,
+            } => {
+                if !self.sent_by(host_chain_id)
+                    || !self.runtime.application_parameters().is_orchestrator(this_chain)
+                    || !self.take_dispatched_match(host_chain_id, guest_chain_id).await
+                {
+                    return;
+                }
+                self.rate_match(match_id, host_chain_id, guest_chain_id, winner_chain_id)
+                    .await;
+                let Some(tournament_id) = tournament_id else {
+                    return;
+                };
+                let Some(mut tournament) = self.tournament(&tournament_id).await else {
+                    return;
+                };
+                let winner_chain_id = winner_chain_id.map(|c| c.to_string());
+                let pairings = tournament.record_result(
+                    &host_chain_id.to_string(),
+                    &guest_chain_id.to_string(),
+                    winner_chain_id.as_deref(),
+                    host_score,
+                    guest_score,
+                );
+                self.dispatch_tournament_matches(&tournament, pairings).await;
+                self.save_tournament(&tournament);
+            }
+
+            CrossChainMessage::TournamentRegister {
+                tournament_id,
+                player_chain_id,
+                player_name,
+            } => {
+                if !self.sent_by(player_chain_id) {
+                    return;
+                }
+                let Some(mut tournament) = self.tournament(&tournament_id).await else {
+                    return;
+                };
+                let player_chain_str = player_chain_id.to_string();
+                if tournament.status != TournamentStatus::Registration
+                    || tournament.is_full()
+                    || tournament.is_registered(&player_chain_str)
+                {
+                    return;
+                }
+                tournament.players.push(PlayerInfo {
+                    chain_id: player_chain_str,
+                    name: player_name,
+                });
+                self.runtime.send_message(
+                    player_chain_id,
+                    CrossChainMessage::TournamentRegistered {
+                        tournament_id: tournament_id.clone(),
+                    },
+                );
+                if tournament.is_full() {
+                    self.start_tournament(tournament).await;
+                } else {
+                    self.save_tournament(&tournament);
+                }
+            }
+
+            CrossChainMessage::TournamentRegistered { tournament_id } => {
+                if !self.sent_by_orchestrator() {
+                    return;
+                }
+                self.state.last_notification.set(Some(format!(
+                    "Registered for tournament {}",
+                    tournament_id
+                )));
+            }
+
+            CrossChainMessage::MatchInvite { invitation } => {
+                let Ok(host) = invitation.host_chain_id.parse() else {
+                    return;
+                };
+                let this_chain = self.runtime.chain_id().to_string();
+                if !self.sent_by(host) || invitation.invitee_chain_id != this_chain {
+                    return;
+                }
+                self.state.last_notification.set(Some(format!(
+                    "Invitation from {}",
+                    invitation.host_name
+                )));
+                self.state
+                    .invitations
+                    .insert(&invitation.match_id.clone(), invitation)
+                    .expect("Failed to store invitation");
+            }
+
+            CrossChainMessage::InviteDeclined {
+                match_id,
+                player_chain_id,
+            } => {
+                if !self.sent_by(player_chain_id) {
+                    return;
+                }
+                let key = Self::invitation_key(&match_id, &player_chain_id.to_string());
+                self.state
+                    .sent_invitations
+                    .remove(&key)
+                    .expect("Failed to remove invitation");
+                self.state
+                    .last_notification
+                    .set(Some(format!("{} declined the invitation", player_chain_id)));
+            }
+
+            CrossChainMessage::RematchRequest {
+                match_id,
+                player_chain_id,
+                swap_host,
+            } => {
+                let Some(game) = self.archived_game(&match_id).await else {
+                    return;
+                };
+                if !self.sent_by_opponent(&game, player_chain_id) {
+                    return;
+                }
+                let requester_chain_id = player_chain_id.to_string();
+                self.state
+                    .rematch_requests
+                    .insert(
+                        &match_id,
+                        RematchRequest {
+                            match_id: match_id.clone(),
+                            requester_chain_id,
+                            swap_host,
+                        },
+                    )
+                    .expect("Failed to store rematch request");
+                self.state
+                    .last_notification
+                    .set(Some("Rematch requested by opponent".to_string()));
+            }
+
+            CrossChainMessage::RematchAccepted {
+                match_id,
+                player_chain_id,
+                swap_host,
+            } => {
+                let Some(game) = self.archived_game(&match_id).await else {
+                    return;
+                };
+                if !self.sent_by_opponent(&game, player_chain_id)
+                    || !self.hosts_rematch(&game, swap_host)
+                {
+                    return;
+                }
+                self.start_rematch(&game);
+            }
         }
     }
 
-    async fn process_streams(
-        &mut self,
-        _streams: Vec<linera_sdk::linera_base_types::StreamUpdate>,
-    ) {
+    async fn process_streams(&mut self, streams: Vec<StreamUpdate>) {
+        let stream_name = StreamName::from(MATCH_STREAM_NAME);
+        for update in streams {
+            if update.stream_id.stream_name != stream_name {
+                continue;
+            }
+            for index in update.new_indices() {
+                let event = self
+                    .runtime
+                    .read_event(update.chain_id, stream_name.clone(), index);
+                let game = event.game();
+                self.state
+                    .spectated
+                    .insert(&game.match_id, game.clone())
+                    .expect("Failed to store spectated match");
+            }
+        }
     }
 
     async fn store(mut self) {
         let _ = self.state.save().await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use linera_sdk::{
+        linera_base_types::{ApplicationId, CryptoHash, Timestamp},
+        util::BlockingWait,
+    };
+
+    use super::*;
+
+    const START_MICROS: u64 = 1_000_000_000;
+
+    fn chain(name: &str) -> ChainId {
+        ChainId(CryptoHash::test_hash(name))
+    }
+
+    /// The application's account, which holds the stakes escrowed on each chain.
+    fn escrow_owner() -> AccountOwner {
+        ApplicationId::new(CryptoHash::test_hash("sps")).into()
+    }
+
+    fn contract_on(chain_id: ChainId) -> SpsContract {
+        let runtime = ContractRuntime::new()
+            .with_chain_id(chain_id)
+            .with_application_id(ApplicationId::new(CryptoHash::test_hash("sps")).with_abi())
+            .with_application_parameters(SpsParameters::default())
+            .with_system_time(Timestamp::from(START_MICROS))
+            .with_chain_balance(Amount::from_tokens(100))
+            .with_owner_balances([(escrow_owner(), Amount::ZERO)]);
+        let mut contract = SpsContract::load(runtime).blocking_wait();
+        contract.instantiate(InstantiationArgument).blocking_wait();
+        contract
+    }
+
+    /// Runs every message `from` has sent to `to` on `to`, with `from` as the origin.
+    fn deliver(from: &mut SpsContract, to: &mut SpsContract) {
+        let origin = from.runtime.chain_id();
+        let destination = to.runtime.chain_id();
+        let messages: Vec<_> = from
+            .runtime
+            .created_send_message_requests()
+            .drain(..)
+            .filter(|request| request.destination == destination)
+            .map(|request| request.message)
+            .collect();
+        for message in messages {
+            receive(to, origin, message);
+        }
+    }
+
+    fn receive(to: &mut SpsContract, origin: ChainId, message: CrossChainMessage) {
+        to.runtime.set_message_origin_chain_id(Some(origin));
+        to.execute_message(message).blocking_wait();
+    }
+
+    fn operate(contract: &mut SpsContract, operation: Operation) {
+        contract.execute_operation(operation).blocking_wait();
+    }
+
+    /// Creates a match on `host` and has `guest` join it, returning the match ID.
+    fn start_match(host: &mut SpsContract, guest: &mut SpsContract) -> String {
+        start_staked_match(host, guest, Amount::ZERO)
+    }
+
+    /// Like `start_match`, with both players putting up `stake`.
+    fn start_staked_match(
+        host: &mut SpsContract,
+        guest: &mut SpsContract,
+        stake: Amount,
+    ) -> String {
+        let match_id = create_match(host, stake);
+        join_match(guest, host, &match_id, stake);
+        deliver(host, guest);
+        assert!(guest.game(&match_id).blocking_wait().is_some());
+        match_id
+    }
+
+    fn create_match(host: &mut SpsContract, stake: Amount) -> String {
+        operate(
+            host,
+            Operation::CreateMatch {
+                host_name: "Host".to_string(),
+                format: None,
+                rules: None,
+                round_timeout_secs: None,
+                stake: Some(stake),
+                room_code: None,
+            },
+        );
+        format!("{}-0", host.runtime.chain_id())
+    }
+
+    /// Has `guest` ask to join `match_id`, with its stake reaching `host`'s escrow along with
+    /// the request.
+    fn join_match(guest: &mut SpsContract, host: &mut SpsContract, match_id: &str, stake: Amount) {
+        operate(
+            guest,
+            Operation::JoinMatch {
+                host_chain_id: host.runtime.chain_id().to_string(),
+                match_id: match_id.to_string(),
+                player_name: "Guest".to_string(),
+                stake: Some(stake),
+                room_code: None,
+            },
+        );
+        let escrowed = host.runtime.owner_balance(escrow_owner());
+        host.runtime
+            .set_owner_balance(escrow_owner(), escrowed.try_add(stake).unwrap());
+        deliver(guest, host);
+    }
+
+    /// Commits `contract`'s player to `choice` for the first round.
+    fn pick(contract: &mut SpsContract, match_id: &str, choice: Choice, salt: &str) {
+        let player_chain_id = contract.runtime.chain_id().to_string();
+        let commitment = choice_commitment(match_id, 1, &player_chain_id, choice, salt);
+        operate(
+            contract,
+            Operation::PickAndReady {
+                match_id: match_id.to_string(),
+                commitment,
+            },
+        );
+    }
+
+    fn reveal(contract: &mut SpsContract, match_id: &str, choice: Choice, salt: &str) {
+        operate(
+            contract,
+            Operation::RevealChoice {
+                match_id: match_id.to_string(),
+                choice,
+                salt: salt.to_string(),
+            },
+        );
+    }
+
+    fn round_deadline(contract: &SpsContract, match_id: &str) -> u64 {
+        let game = contract.game(match_id).blocking_wait().unwrap();
+        game.round_deadline_micros.expect("Round has no deadline")
+    }
+
+    fn claim_timeout(
+        contract: &mut SpsContract,
+        match_id: &str,
+        at_micros: u64,
+        claim_match: bool,
+    ) {
+        contract.runtime.set_system_time(Timestamp::from(at_micros));
+        operate(
+            contract,
+            Operation::ClaimTimeout {
+                match_id: match_id.to_string(),
+                claim_match,
+            },
+        );
+    }
+
+    fn archived(contract: &SpsContract, match_id: &str) -> Game {
+        let position = contract
+            .state
+            .archive_index
+            .get(match_id)
+            .blocking_wait()
+            .unwrap()
+            .expect("Match was not archived");
+        contract
+            .state
+            .archive
+            .get(position)
+            .blocking_wait()
+            .unwrap()
+            .unwrap()
+    }
+
+    fn enqueue(orchestrator: &mut SpsContract, player: ChainId, at_micros: u64) {
+        orchestrator
+            .runtime
+            .set_system_time(Timestamp::from(at_micros));
+        receive(
+            orchestrator,
+            player,
+            CrossChainMessage::MatchmakingEnqueue {
+                player_chain_id: player,
+                player_name: player.to_string(),
+                format: MatchFormat::default(),
+            },
+        );
+    }
+
+    #[test]
+    fn declined_ready_check_requeues_with_the_original_enqueue_time() {
+        let mut orchestrator = contract_on(chain("orchestrator"));
+        enqueue(&mut orchestrator, chain("first"), START_MICROS);
+        enqueue(&mut orchestrator, chain("second"), START_MICROS + 1_000_000);
+        let check_id = format!("{}-check-0", chain("orchestrator"));
+        assert!(orchestrator
+            .state
+            .pending_matches
+            .contains_key(&check_id)
+            .blocking_wait()
+            .unwrap());
+
+        for (player, accept) in [(chain("first"), true), (chain("second"), false)] {
+            orchestrator
+                .runtime
+                .set_system_time(Timestamp::from(START_MICROS + 5_000_000));
+            receive(
+                &mut orchestrator,
+                player,
+                CrossChainMessage::ReadyCheckResponse {
+                    check_id: check_id.clone(),
+                    player_chain_id: player,
+                    accept,
+                },
+            );
+        }
+
+        let queue = orchestrator.state.matchmaking_queue.get();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].chain_id, chain("first").to_string());
+        assert_eq!(queue[0].enqueued_at_micros, START_MICROS);
+    }
+
+    #[test]
+    #[should_panic(expected = "Choice does not match commitment")]
+    fn revealing_a_different_choice_than_committed_is_rejected() {
+        let mut host = contract_on(chain("host"));
+        let mut guest = contract_on(chain("guest"));
+        let match_id = start_match(&mut host, &mut guest);
+        pick(&mut host, &match_id, Choice::STONE, "host-salt");
+        pick(&mut guest, &match_id, Choice::PAPER, "guest-salt");
+        deliver(&mut guest, &mut host);
+
+        reveal(&mut host, &match_id, Choice::SCISSORS, "host-salt");
+    }
+
+    #[test]
+    #[should_panic(expected = "Choice already revealed")]
+    fn revealing_twice_is_rejected() {
+        let mut host = contract_on(chain("host"));
+        let mut guest = contract_on(chain("guest"));
+        let match_id = start_match(&mut host, &mut guest);
+        pick(&mut host, &match_id, Choice::STONE, "host-salt");
+        pick(&mut guest, &match_id, Choice::PAPER, "guest-salt");
+        deliver(&mut host, &mut guest);
+
+        reveal(&mut guest, &match_id, Choice::PAPER, "guest-salt");
+        reveal(&mut guest, &match_id, Choice::PAPER, "guest-salt");
+    }
+
+    #[test]
+    #[should_panic(expected = "Opponent has not committed yet")]
+    fn revealing_before_the_opponent_commits_is_rejected() {
+        let mut host = contract_on(chain("host"));
+        let mut guest = contract_on(chain("guest"));
+        let match_id = start_match(&mut host, &mut guest);
+        pick(&mut host, &match_id, Choice::STONE, "host-salt");
+
+        reveal(&mut host, &match_id, Choice::STONE, "host-salt");
+    }
+
+    #[test]
+    fn a_reveal_that_does_not_match_its_commitment_forfeits_the_round() {
+        let mut host = contract_on(chain("host"));
+        let mut guest = contract_on(chain("guest"));
+        let match_id = start_match(&mut host, &mut guest);
+        pick(&mut host, &match_id, Choice::STONE, "host-salt");
+        pick(&mut guest, &match_id, Choice::PAPER, "guest-salt");
+        deliver(&mut guest, &mut host);
+        reveal(&mut host, &match_id, Choice::STONE, "host-salt");
+
+        receive(
+            &mut host,
+            chain("guest"),
+            CrossChainMessage::ChoiceReveal {
+                match_id: match_id.clone(),
+                player_chain_id: chain("guest"),
+                round: 1,
+                choice: Choice::PAPER,
+                salt: "another-salt".to_string(),
+            },
+        );
+
+        let game = host.game(&match_id).blocking_wait().unwrap();
+        let record = &game.history[0];
+        assert_eq!(record.outcome, RoundOutcome::HostWins);
+        assert_eq!(record.forfeited_by, Some(chain("guest").to_string()));
+        assert_eq!((game.host_score, game.guest_score), (1, 0));
+    }
+
+    #[test]
+    fn match_messages_from_a_spoofed_origin_are_ignored() {
+        let mut host = contract_on(chain("host"));
+        let mut guest = contract_on(chain("guest"));
+        let match_id = start_match(&mut host, &mut guest);
+        pick(&mut host, &match_id, Choice::STONE, "host-salt");
+        let snapshot = |contract: &SpsContract| {
+            let game = contract.game(&match_id).blocking_wait();
+            let round_state = contract.round_state(&match_id).blocking_wait();
+            bcs::to_bytes(&(game, round_state)).unwrap()
+        };
+        let (host_before, guest_before) = (snapshot(&host), snapshot(&guest));
+
+        let commitment = choice_commitment(
+            &match_id,
+            1,
+            &chain("guest").to_string(),
+            Choice::PAPER,
+            "guest-salt",
+        );
+        for message in [
+            CrossChainMessage::Resignation {
+                match_id: match_id.clone(),
+                player_chain_id: chain("guest"),
+            },
+            CrossChainMessage::ReadyNotice {
+                match_id: match_id.clone(),
+                player_chain_id: chain("guest"),
+                round: 1,
+                commitment,
+            },
+            CrossChainMessage::ChoiceReveal {
+                match_id: match_id.clone(),
+                player_chain_id: chain("guest"),
+                round: 1,
+                choice: Choice::PAPER,
+                salt: "guest-salt".to_string(),
+            },
+        ] {
+            receive(&mut host, chain("intruder"), message);
+        }
+        let mut forged = host.game(&match_id).blocking_wait().unwrap();
+        forged.status = MatchStatus::Ended;
+        forged.winner_chain_id = Some(chain("guest").to_string());
+        receive(
+            &mut guest,
+            chain("intruder"),
+            CrossChainMessage::GameSync { game: forged },
+        );
+
+        assert_eq!(snapshot(&host), host_before);
+        assert_eq!(snapshot(&guest), guest_before);
+    }
+
+    #[test]
+    fn a_join_rejection_from_another_chain_is_ignored() {
+        let mut guest = contract_on(chain("guest"));
+        let match_id = format!("{}-0", chain("host"));
+        operate(
+            &mut guest,
+            Operation::JoinMatch {
+                host_chain_id: chain("host").to_string(),
+                match_id: match_id.clone(),
+                player_name: "Guest".to_string(),
+                stake: None,
+                room_code: None,
+            },
+        );
+
+        receive(
+            &mut guest,
+            chain("intruder"),
+            CrossChainMessage::JoinRejected {
+                match_id: match_id.clone(),
+                reason: JoinError::MatchFull,
+            },
+        );
+
+        assert!(guest
+            .state
+            .pending_joins
+            .contains_key(&match_id)
+            .blocking_wait()
+            .unwrap());
+        assert!(guest.state.last_join_rejection.get().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Round deadline has not passed")]
+    fn claiming_a_timeout_before_the_deadline_is_rejected() {
+        let mut host = contract_on(chain("host"));
+        let mut guest = contract_on(chain("guest"));
+        let match_id = start_match(&mut host, &mut guest);
+        pick(&mut host, &match_id, Choice::STONE, "host-salt");
+
+        let deadline = round_deadline(&host, &match_id);
+        claim_timeout(&mut host, &match_id, deadline, false);
+    }
+
+    #[test]
+    fn the_host_wins_the_round_once_the_deadline_passes() {
+        let mut host = contract_on(chain("host"));
+        let mut guest = contract_on(chain("guest"));
+        let match_id = start_match(&mut host, &mut guest);
+        pick(&mut host, &match_id, Choice::STONE, "host-salt");
+
+        let deadline = round_deadline(&host, &match_id);
+        claim_timeout(&mut host, &match_id, deadline + 1, false);
+        deliver(&mut host, &mut guest);
+
+        for contract in [&host, &guest] {
+            let game = contract.game(&match_id).blocking_wait().unwrap();
+            assert_eq!((game.host_score, game.guest_score), (1, 0));
+            assert_eq!(
+                game.history[0].forfeited_by,
+                Some(chain("guest").to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn a_guest_timeout_claim_is_checked_against_the_host_clock() {
+        let mut host = contract_on(chain("host"));
+        let mut guest = contract_on(chain("guest"));
+        let match_id = start_match(&mut host, &mut guest);
+        pick(&mut guest, &match_id, Choice::PAPER, "guest-salt");
+        deliver(&mut guest, &mut host);
+        let deadline = round_deadline(&host, &match_id);
+
+        // The guest's clock is ahead of the host's, so the host turns the claim down.
+        host.runtime.set_system_time(Timestamp::from(deadline));
+        claim_timeout(&mut guest, &match_id, deadline + 1, true);
+        deliver(&mut guest, &mut host);
+        assert_eq!(
+            host.game(&match_id).blocking_wait().unwrap().status,
+            MatchStatus::Active
+        );
+
+        host.runtime.set_system_time(Timestamp::from(deadline + 1));
+        claim_timeout(&mut guest, &match_id, deadline + 1, true);
+        deliver(&mut guest, &mut host);
+        deliver(&mut host, &mut guest);
+
+        for contract in [&host, &guest] {
+            let game = archived(contract, &match_id);
+            assert_eq!(game.status, MatchStatus::Ended);
+            assert_eq!(game.winner_chain_id, Some(chain("guest").to_string()));
+        }
+    }
+
+    #[test]
+    fn the_winner_takes_both_stakes() {
+        let mut host = contract_on(chain("host"));
+        let mut guest = contract_on(chain("guest"));
+        let match_id = start_staked_match(&mut host, &mut guest, Amount::from_tokens(10));
+        assert_eq!(
+            host.runtime.owner_balance(escrow_owner()),
+            Amount::from_tokens(20)
+        );
+
+        operate(&mut host, Operation::LeaveMatch { match_id });
+
+        assert_eq!(host.runtime.owner_balance(escrow_owner()), Amount::ZERO);
+        assert_eq!(host.runtime.chain_balance(), Amount::from_tokens(90));
+        assert_eq!(
+            host.runtime
+                .outgoing_transfers()
+                .get(&Account::chain(chain("guest"))),
+            Some(&Amount::from_tokens(20))
+        );
+        assert_eq!(guest.runtime.chain_balance(), Amount::from_tokens(90));
+    }
+
+    #[test]
+    fn a_draw_refunds_each_stake() {
+        let mut host = contract_on(chain("host"));
+        let mut guest = contract_on(chain("guest"));
+        let match_id = start_staked_match(&mut host, &mut guest, Amount::from_tokens(10));
+
+        operate(
+            &mut host,
+            Operation::OfferDraw {
+                match_id: match_id.clone(),
+            },
+        );
+        deliver(&mut host, &mut guest);
+        operate(
+            &mut guest,
+            Operation::AcceptDraw {
+                match_id: match_id.clone(),
+            },
+        );
+        deliver(&mut guest, &mut host);
+
+        assert_eq!(archived(&host, &match_id).winner_chain_id, None);
+        assert_eq!(host.runtime.owner_balance(escrow_owner()), Amount::ZERO);
+        assert_eq!(host.runtime.chain_balance(), Amount::from_tokens(100));
+        assert_eq!(
+            host.runtime
+                .outgoing_transfers()
+                .get(&Account::chain(chain("guest"))),
+            Some(&Amount::from_tokens(10))
+        );
+    }
+
+    #[test]
+    fn a_rejected_join_refunds_the_guest_stake() {
+        let mut host = contract_on(chain("host"));
+        let mut guest = contract_on(chain("guest"));
+        let match_id = create_match(&mut host, Amount::from_tokens(10));
+
+        join_match(&mut guest, &mut host, &match_id, Amount::from_tokens(5));
+        deliver(&mut host, &mut guest);
+
+        assert_eq!(
+            host.runtime.owner_balance(escrow_owner()),
+            Amount::from_tokens(10)
+        );
+        assert_eq!(host.runtime.chain_balance(), Amount::from_tokens(90));
+        assert_eq!(
+            host.runtime
+                .outgoing_transfers()
+                .get(&Account::chain(chain("guest"))),
+            Some(&Amount::from_tokens(5))
+        );
+        let rejection = guest.state.last_join_rejection.get().clone().unwrap();
+        assert_eq!(rejection.reason, JoinError::StakeMismatch);
+    }
+
+    #[test]
+    fn only_results_for_dispatched_matches_are_rated() {
+        let mut orchestrator = contract_on(chain("orchestrator"));
+        enqueue(&mut orchestrator, chain("first"), START_MICROS);
+        enqueue(&mut orchestrator, chain("second"), START_MICROS);
+        let check_id = format!("{}-check-0", chain("orchestrator"));
+        for player in [chain("first"), chain("second")] {
+            receive(
+                &mut orchestrator,
+                player,
+                CrossChainMessage::ReadyCheckResponse {
+                    check_id: check_id.clone(),
+                    player_chain_id: player,
+                    accept: true,
+                },
+            );
+        }
+        let host = orchestrator
+            .runtime
+            .created_send_message_requests()
+            .iter()
+            .find(|request| matches!(request.message, CrossChainMessage::MatchmakingStart { .. }))
+            .expect("Match was not dispatched")
+            .destination;
+        let guest = if host == chain("first") {
+            chain("second")
+        } else {
+            chain("first")
+        };
+        let result = |match_id: &str, host_chain_id: ChainId, guest_chain_id: ChainId| {
+            CrossChainMessage::MatchResult {
+                match_id: match_id.to_string(),
+                host_chain_id,
+                guest_chain_id,
+                winner_chain_id: Some(host_chain_id),
+                host_score: 2,
+                guest_score: 0,
+                tournament_id: None,
+            }
+        };
+        let is_rated = |orchestrator: &SpsContract, match_id: &str| {
+            orchestrator
+                .state
+                .rated_matches
+                .contains(match_id)
+                .blocking_wait()
+                .unwrap()
+        };
+
+        receive(
+            &mut orchestrator,
+            chain("other"),
+            result("other-0", chain("other"), guest),
+        );
+        receive(&mut orchestrator, host, result("real-0", host, guest));
+        receive(&mut orchestrator, host, result("forged-0", host, guest));
+
+        assert!(!is_rated(&orchestrator, "other-0"));
+        assert!(is_rated(&orchestrator, "real-0"));
+        assert!(!is_rated(&orchestrator, "forged-0"));
+        let history = orchestrator
+            .state
+            .rating_history
+            .get(&host.to_string())
+            .blocking_wait()
+            .unwrap()
+            .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].match_id, "real-0");
+    }
+
+    #[test]
+    fn leaving_an_active_match_resigns_it() {
+        let mut host = contract_on(chain("host"));
+        let mut guest = contract_on(chain("guest"));
+        let match_id = start_match(&mut host, &mut guest);
+
+        operate(
+            &mut guest,
+            Operation::LeaveMatch {
+                match_id: match_id.clone(),
+            },
+        );
+        deliver(&mut guest, &mut host);
+        deliver(&mut host, &mut guest);
+
+        for contract in [&host, &guest] {
+            let game = archived(contract, &match_id);
+            assert_eq!(game.status, MatchStatus::Ended);
+            assert_eq!(game.winner_chain_id, Some(chain("host").to_string()));
+        }
+    }
+
+    #[test]
+    fn host_leaving_an_active_match_hands_the_guest_the_win() {
+        let mut host = contract_on(chain("host"));
+        let mut guest = contract_on(chain("guest"));
+        let match_id = start_match(&mut host, &mut guest);
+
+        operate(
+            &mut host,
+            Operation::LeaveMatch {
+                match_id: match_id.clone(),
+            },
+        );
+        deliver(&mut host, &mut guest);
+
+        for contract in [&host, &guest] {
+            let game = archived(contract, &match_id);
+            assert_eq!(game.status, MatchStatus::Ended);
+            assert_eq!(game.winner_chain_id, Some(chain("guest").to_string()));
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use async_graphql::{Request, Response};
-use linera_sdk::linera_base_types::{ChainId, ContractAbi, ServiceAbi};
+use linera_sdk::linera_base_types::{
+    Amount, BcsHashable, ChainId, ContractAbi, CryptoHash, ServiceAbi,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 pub struct SpsAbi;
 
@@ -14,8 +17,34 @@ impl ServiceAbi for SpsAbi {
     type QueryResponse = Response;
 }
 
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
-pub struct SpsParameters;
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SpsParameters {
+    /// Format used when a match is created without an explicit one.
+    pub default_format: MatchFormat,
+    /// Time each player has to act in a round before the opponent may claim it. Zero disables
+    /// round deadlines.
+    pub default_round_timeout_secs: u64,
+    /// Chains trusted to run matchmaking. The first one is the default target of
+    /// `SearchPlayer`; an empty list accepts any orchestrator.
+    pub orchestrator_chain_ids: Vec<ChainId>,
+}
+
+impl Default for SpsParameters {
+    fn default() -> Self {
+        SpsParameters {
+            default_format: MatchFormat::default(),
+            default_round_timeout_secs: 120,
+            orchestrator_chain_ids: Vec::new(),
+        }
+    }
+}
+
+impl SpsParameters {
+    pub fn is_orchestrator(&self, chain_id: ChainId) -> bool {
+        self.orchestrator_chain_ids.is_empty() || self.orchestrator_chain_ids.contains(&chain_id)
+    }
+}
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct InstantiationArgument;
@@ -25,13 +54,20 @@ pub enum MatchStatus {
     WaitingForPlayer,
     Active,
     Ended,
+    /// A player left before the match was decided.
+    Abandoned,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, async_graphql::Enum)]
-pub enum Choice {
-    Stone,
-    Paper,
-    Scissors,
+/// A move, identified by its index in the match's [`GameRules`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Choice(pub u8);
+
+async_graphql::scalar!(Choice);
+
+impl Choice {
+    pub const STONE: Choice = Choice(0);
+    pub const PAPER: Choice = Choice(1);
+    pub const SCISSORS: Choice = Choice(2);
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, async_graphql::Enum)]
@@ -41,16 +77,197 @@ pub enum RoundOutcome {
     GuestWins,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum MatchFormatKind {
+    /// The first player to win `rounds` rounds wins the match.
+    FirstTo,
+    /// At most `rounds` rounds are played; a majority of them wins the match early.
+    BestOf,
+    /// Exactly `rounds` rounds are played and the higher score wins.
+    FixedRounds,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    async_graphql::SimpleObject,
+    async_graphql::InputObject,
+)]
+#[graphql(input_name = "MatchFormatInput", rename_fields = "camelCase")]
+pub struct MatchFormat {
+    pub kind: MatchFormatKind,
+    pub rounds: u8,
+}
+
+impl Default for MatchFormat {
+    fn default() -> Self {
+        MatchFormat {
+            kind: MatchFormatKind::FirstTo,
+            rounds: 3,
+        }
+    }
+}
+
+impl MatchFormat {
+    pub fn is_valid(&self) -> bool {
+        self.rounds > 0
+    }
+
+    /// Returns the match result once the format's end condition is reached after
+    /// `rounds_played` rounds, or `None` if the match goes on.
+    pub fn result(
+        &self,
+        host_score: u8,
+        guest_score: u8,
+        rounds_played: u8,
+    ) -> Option<RoundOutcome> {
+        let by_score = || match host_score.cmp(&guest_score) {
+            std::cmp::Ordering::Greater => RoundOutcome::HostWins,
+            std::cmp::Ordering::Less => RoundOutcome::GuestWins,
+            std::cmp::Ordering::Equal => RoundOutcome::Draw,
+        };
+        match self.kind {
+            MatchFormatKind::FirstTo => {
+                (host_score >= self.rounds || guest_score >= self.rounds).then(by_score)
+            }
+            MatchFormatKind::BestOf => {
+                let majority = self.rounds / 2 + 1;
+                (host_score >= majority || guest_score >= majority || rounds_played >= self.rounds)
+                    .then(by_score)
+            }
+            MatchFormatKind::FixedRounds => (rounds_played >= self.rounds).then(by_score),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum RuleSetKind {
+    /// Stone, Paper, Scissors.
+    Classic,
+    /// Rock, Paper, Scissors, Spock, Lizard.
+    LizardSpock,
+    /// Any odd number of moves where each move beats the moves an odd distance behind it.
+    Cyclic,
+    /// An explicit payoff table.
+    Custom,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    async_graphql::SimpleObject,
+    async_graphql::InputObject,
+)]
+#[graphql(input_name = "GameRulesInput", rename_fields = "camelCase")]
+pub struct GameRules {
+    pub kind: RuleSetKind,
+    /// Move names, indexed by `Choice`.
+    pub moves: Vec<String>,
+    /// Row-major `moves x moves` table for custom rules: 1 if the row move beats the column
+    /// move, -1 if it loses to it and 0 for a draw. Empty for the other kinds.
+    pub payoffs: Vec<i8>,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        GameRules::classic()
+    }
+}
+
+impl GameRules {
+    pub fn classic() -> Self {
+        GameRules {
+            kind: RuleSetKind::Classic,
+            moves: ["Stone", "Paper", "Scissors"].map(String::from).to_vec(),
+            payoffs: Vec::new(),
+        }
+    }
+
+    pub fn lizard_spock() -> Self {
+        GameRules {
+            kind: RuleSetKind::LizardSpock,
+            moves: ["Rock", "Paper", "Scissors", "Spock", "Lizard"]
+                .map(String::from)
+                .to_vec(),
+            payoffs: Vec::new(),
+        }
+    }
+
+    /// Fills in the presets and checks that the rules are consistent, so that both chains
+    /// resolve every pair of moves the same way.
+    pub fn normalized(self) -> Option<GameRules> {
+        let n = self.moves.len();
+        match self.kind {
+            RuleSetKind::Classic => Some(GameRules::classic()),
+            RuleSetKind::LizardSpock => Some(GameRules::lizard_spock()),
+            RuleSetKind::Cyclic => (n >= 3 && n % 2 == 1 && n <= u8::MAX as usize).then(|| {
+                GameRules {
+                    payoffs: Vec::new(),
+                    ..self
+                }
+            }),
+            RuleSetKind::Custom => {
+                let consistent = n >= 2
+                    && n <= u8::MAX as usize
+                    && self.payoffs.len() == n * n
+                    && (0..n).all(|a| {
+                        (0..n).all(|b| {
+                            let payoff = self.payoffs[a * n + b];
+                            (-1..=1).contains(&payoff) && payoff == -self.payoffs[b * n + a]
+                        })
+                    });
+                consistent.then_some(self)
+            }
+        }
+    }
+
+    pub fn is_move(&self, choice: Choice) -> bool {
+        (choice.0 as usize) < self.moves.len()
+    }
+
+    pub fn outcome(&self, host_choice: Choice, guest_choice: Choice) -> RoundOutcome {
+        let n = self.moves.len();
+        let (host, guest) = (host_choice.0 as usize, guest_choice.0 as usize);
+        let payoff = match self.kind {
+            RuleSetKind::Custom => self.payoffs[host * n + guest],
+            _ => match (host + n - guest) % n {
+                0 => 0,
+                distance if distance % 2 == 1 => 1,
+                _ => -1,
+            },
+        };
+        match payoff {
+            1 => RoundOutcome::HostWins,
+            -1 => RoundOutcome::GuestWins,
+            _ => RoundOutcome::Draw,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
 #[graphql(rename_fields = "camelCase")]
 pub struct RoundRecord {
     pub round: u8,
-    pub host_choice: Choice,
-    pub guest_choice: Choice,
+    /// `None` if the host never revealed a choice before the round was claimed on timeout.
+    pub host_choice: Option<Choice>,
+    /// `None` if the guest never revealed a choice before the round was claimed on timeout.
+    pub guest_choice: Option<Choice>,
     pub outcome: RoundOutcome,
     pub host_score: u8,
     pub guest_score: u8,
     pub timestamp: String,
+    /// Chain of the player who forfeited the round, either by revealing a choice that did not
+    /// match their commitment or by letting the round deadline pass.
+    pub forfeited_by: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
@@ -66,6 +283,8 @@ pub struct Game {
     pub match_id: String,
     pub host_chain_id: String,
     pub status: MatchStatus,
+    pub format: MatchFormat,
+    pub rules: GameRules,
     pub players: Vec<PlayerInfo>,
     pub round: u8,
     pub host_score: u8,
@@ -76,18 +295,846 @@ pub struct Game {
     pub last_outcome: Option<RoundOutcome>,
     pub history: Vec<RoundRecord>,
     pub winner_chain_id: Option<String>,
+    /// Time each player has to act in a round; zero means rounds never time out.
+    pub round_timeout_micros: u64,
+    pub round_deadline_micros: Option<u64>,
+    /// Orchestrator the host reports the final result to, for matches made by one.
+    pub orchestrator_chain_id: Option<String>,
+    /// Native tokens each player escrows on the host chain; the winner takes both stakes.
+    pub stake: Amount,
+    /// Orchestrator whose public lobby lists this match while it waits for a guest.
+    pub lobby_chain_id: Option<String>,
+    /// Hash of the private room code a guest must supply to join, if any. It is public along
+    /// with the rest of the game, so it does not hide a short code from guessing.
+    pub room_code_hash: Option<CryptoHash>,
+    /// Tournament this match belongs to, reported back with the result.
+    pub tournament_id: Option<String>,
+}
+
+impl Game {
+    pub fn guest_chain_id(&self) -> Option<&str> {
+        self.players
+            .iter()
+            .map(|p| p.chain_id.as_str())
+            .find(|c| *c != self.host_chain_id)
+    }
+}
+
+/// Name of the event stream on which hosts publish their matches for spectators.
+pub const MATCH_STREAM_NAME: &[u8] = b"matches";
+
+/// Events published by a host chain on the [`MATCH_STREAM_NAME`] stream. Each one carries the
+/// full game so a spectator can render the match from the latest event alone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MatchEvent {
+    MatchCreated { game: Game },
+    PlayerJoined { game: Game },
+    RoundResolved { game: Game },
+    MatchEnded { game: Game },
+}
+
+impl MatchEvent {
+    pub fn game(&self) -> &Game {
+        match self {
+            MatchEvent::MatchCreated { game }
+            | MatchEvent::PlayerJoined { game }
+            | MatchEvent::RoundResolved { game }
+            | MatchEvent::MatchEnded { game } => game,
+        }
+    }
+}
+
+pub const INITIAL_RATING: i32 = 1200;
+pub const ELO_K_FACTOR: f64 = 32.0;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum)]
+pub enum GameResult {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl GameResult {
+    fn score(self) -> f64 {
+        match self {
+            GameResult::Win => 1.0,
+            GameResult::Loss => 0.0,
+            GameResult::Draw => 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct RatingChange {
+    pub match_id: String,
+    pub opponent_chain_id: String,
+    pub result: GameResult,
+    pub old_rating: i32,
+    pub new_rating: i32,
+    pub timestamp: String,
+}
+
+/// A player's Elo rating and record as tracked by an orchestrator chain.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct PlayerRating {
+    pub chain_id: String,
+    pub rating: i32,
+    pub games_played: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    /// Positive for a run of wins, negative for a run of losses; a draw resets it.
+    pub current_streak: i32,
+}
+
+impl PlayerRating {
+    pub fn new(chain_id: String) -> Self {
+        PlayerRating {
+            chain_id,
+            rating: INITIAL_RATING,
+            games_played: 0,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+            current_streak: 0,
+        }
+    }
+
+    pub fn record(&mut self, result: GameResult) {
+        self.games_played += 1;
+        match result {
+            GameResult::Win => {
+                self.wins += 1;
+                self.current_streak = self.current_streak.max(0) + 1;
+            }
+            GameResult::Loss => {
+                self.losses += 1;
+                self.current_streak = self.current_streak.min(0) - 1;
+            }
+            GameResult::Draw => {
+                self.draws += 1;
+                self.current_streak = 0;
+            }
+        }
+    }
+
+    /// Share of games won, counting draws as half a win.
+    pub fn win_rate(&self) -> f64 {
+        if self.games_played == 0 {
+            return 0.0;
+        }
+        (f64::from(self.wins) + f64::from(self.draws) / 2.0) / f64::from(self.games_played)
+    }
+}
+
+/// How long a player stays in the matchmaking queue without being paired.
+pub const MATCHMAKING_ENTRY_TTL_MICROS: u64 = 5 * 60 * 1_000_000;
+
+/// How long both paired players have to accept a ready check.
+pub const READY_CHECK_WINDOW_MICROS: u64 = 30 * 1_000_000;
+
+/// Rating gap a queued player accepts right after enqueueing.
+pub const MATCHMAKING_BASE_WINDOW: u32 = 100;
+/// How much the accepted rating gap grows per second spent in the queue.
+pub const MATCHMAKING_WINDOW_GROWTH_PER_SEC: u32 = 5;
+
+/// Returns the rating gap a player accepts after waiting `waited_micros` in the queue.
+pub fn rating_window(waited_micros: u64) -> u32 {
+    let waited_secs = u32::try_from(waited_micros / 1_000_000).unwrap_or(u32::MAX);
+    MATCHMAKING_BASE_WINDOW
+        .saturating_add(waited_secs.saturating_mul(MATCHMAKING_WINDOW_GROWTH_PER_SEC))
+}
+
+/// Returns the new Elo rating of a player rated `rating` after a game with the given `result`
+/// against an opponent rated `opponent_rating`.
+pub fn elo_rating(rating: i32, opponent_rating: i32, result: GameResult) -> i32 {
+    let expected = 1.0 / (1.0 + 10f64.powf(f64::from(opponent_rating - rating) / 400.0));
+    rating + (ELO_K_FACTOR * (result.score() - expected)).round() as i32
+}
+
+pub const MAX_BRACKET_SIZE: u32 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum TournamentKind {
+    /// Knockout bracket: losers are out, the last player standing is champion.
+    SingleElimination,
+    /// League where every player meets every other once; the standings leader is champion.
+    RoundRobin,
+    /// A fixed number of rounds, each pairing players on equal points who have not met yet.
+    Swiss,
+}
+
+/// League points for a win and a draw.
+pub const LEAGUE_WIN_POINTS: u32 = 3;
+pub const LEAGUE_DRAW_POINTS: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum TournamentStatus {
+    Registration,
+    InProgress,
+    Finished,
+}
+
+/// A match between two players of a tournament, identified by its round (starting at 1) and
+/// its slot within the round.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct TournamentMatch {
+    pub round: u32,
+    pub slot: u32,
+    pub host: Option<PlayerInfo>,
+    pub guest: Option<PlayerInfo>,
+    pub winner_chain_id: Option<String>,
+    pub host_score: u8,
+    pub guest_score: u8,
+    /// Whether the pairing was sent to the host chain.
+    pub dispatched: bool,
+    /// Whether the match has a final result (or was a bye).
+    pub completed: bool,
+}
+
+impl TournamentMatch {
+    fn new(round: u32, slot: u32) -> Self {
+        TournamentMatch {
+            round,
+            slot,
+            host: None,
+            guest: None,
+            winner_chain_id: None,
+            host_score: 0,
+            guest_score: 0,
+            dispatched: false,
+            completed: false,
+        }
+    }
+
+    /// League points `chain_id` earned from this match, if it played in it and it completed.
+    fn points_of(&self, chain_id: &str) -> Option<u32> {
+        let played = [&self.host, &self.guest]
+            .iter()
+            .any(|p| p.as_ref().is_some_and(|p| p.chain_id == chain_id));
+        if !self.completed || !played {
+            return None;
+        }
+        Some(match self.winner_chain_id.as_deref() {
+            None => LEAGUE_DRAW_POINTS,
+            Some(winner) if winner == chain_id => LEAGUE_WIN_POINTS,
+            Some(_) => 0,
+        })
+    }
+
+    /// The player `chain_id` faced in this match, if any.
+    fn opponent_of(&self, chain_id: &str) -> Option<&PlayerInfo> {
+        match (&self.host, &self.guest) {
+            (Some(host), Some(guest)) if host.chain_id == chain_id => Some(guest),
+            (Some(host), Some(guest)) if guest.chain_id == chain_id => Some(host),
+            _ => None,
+        }
+    }
+
+    fn is_between(&self, host_chain_id: &str, guest_chain_id: &str) -> bool {
+        self.host.as_ref().is_some_and(|p| p.chain_id == host_chain_id)
+            && self.guest.as_ref().is_some_and(|p| p.chain_id == guest_chain_id)
+    }
+}
+
+/// A host and a guest the orchestrator should start a match between.
+pub type Pairing = (PlayerInfo, PlayerInfo);
+
+/// A player's line in a league table.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct Standing {
+    pub chain_id: String,
+    pub name: String,
+    pub played: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub points: u32,
+    pub rounds_won: u32,
+    pub rounds_lost: u32,
+    pub round_difference: i32,
+    /// Points earned against the players level on points, the first league tiebreak.
+    pub head_to_head_points: u32,
+    /// Sum of the points of every opponent faced, the first Swiss tiebreak.
+    pub buchholz: u32,
+}
+
+/// A tournament run by an orchestrator chain.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct Tournament {
+    pub tournament_id: String,
+    pub name: String,
+    pub kind: TournamentKind,
+    pub format: MatchFormat,
+    /// Maximum number of players. Once a knockout starts, its bracket is shrunk to the smallest
+    /// power of two that fits the registered players.
+    pub bracket_size: u32,
+    /// Number of rounds a Swiss tournament plays, set when it starts.
+    pub swiss_rounds: u32,
+    pub status: TournamentStatus,
+    /// Registered players, in seed order once the tournament started.
+    pub players: Vec<PlayerInfo>,
+    /// Every match of the bracket or every league fixture, round by round.
+    pub matches: Vec<TournamentMatch>,
+    pub champion_chain_id: Option<String>,
+}
+
+impl Tournament {
+    pub fn new(
+        tournament_id: String,
+        name: String,
+        kind: TournamentKind,
+        format: MatchFormat,
+        bracket_size: u32,
+    ) -> Self {
+        Tournament {
+            tournament_id,
+            name,
+            kind,
+            format,
+            bracket_size,
+            swiss_rounds: 0,
+            status: TournamentStatus::Registration,
+            players: Vec::new(),
+            matches: Vec::new(),
+            champion_chain_id: None,
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.players.len() >= self.bracket_size as usize
+    }
+
+    pub fn is_registered(&self, chain_id: &str) -> bool {
+        self.players.iter().any(|p| p.chain_id == chain_id)
+    }
+
+    fn rounds(&self) -> u32 {
+        self.bracket_size.trailing_zeros()
+    }
+
+    fn current_round(&self) -> u32 {
+        self.matches.iter().map(|m| m.round).max().unwrap_or(0)
+    }
+
+    fn match_mut(&mut self, round: u32, slot: u32) -> &mut TournamentMatch {
+        self.matches
+            .iter_mut()
+            .find(|m| m.round == round && m.slot == slot)
+            .expect("Bracket slot not found")
+    }
+
+    /// Closes registration with `seeded` players, best first. Returns the pairings to start
+    /// right away; league fixtures are started on demand with [`Tournament::play_fixtures`].
+    pub fn start(&mut self, seeded: Vec<PlayerInfo>) -> Vec<Pairing> {
+        match self.kind {
+            TournamentKind::SingleElimination => self.seed(seeded),
+            TournamentKind::RoundRobin => {
+                self.schedule_round_robin(seeded);
+                Vec::new()
+            }
+            TournamentKind::Swiss => {
+                self.swiss_rounds = seeded.len().next_power_of_two().trailing_zeros().max(1);
+                self.players = seeded;
+                self.status = TournamentStatus::InProgress;
+                self.pair_swiss_round()
+            }
+        }
+    }
+
+    /// Builds the bracket so the top seeds meet last and get the byes. Returns the first-round
+    /// pairings to start.
+    fn seed(&mut self, seeded: Vec<PlayerInfo>) -> Vec<Pairing> {
+        self.bracket_size = (seeded.len() as u32).max(2).next_power_of_two();
+        self.players = seeded;
+        self.status = TournamentStatus::InProgress;
+        self.matches = (1..=self.rounds())
+            .flat_map(|round| (0..self.bracket_size >> round).map(move |s| (round, s)))
+            .map(|(round, slot)| TournamentMatch::new(round, slot))
+            .collect();
+
+        // Seed order of the bracket lines, e.g. 1 8 4 5 2 7 3 6 for eight players.
+        let mut order = vec![1u32];
+        while (order.len() as u32) < self.bracket_size {
+            let size = order.len() as u32 * 2;
+            order = order.iter().flat_map(|s| [*s, size + 1 - s]).collect();
+        }
+        let mut pairings = Vec::new();
+        for (slot, seeds) in order.chunks(2).enumerate() {
+            let host = self.players.get(seeds[0] as usize - 1).cloned();
+            let guest = self.players.get(seeds[1] as usize - 1).cloned();
+            let first = self.match_mut(1, slot as u32);
+            first.host = host.clone();
+            first.guest = guest.clone();
+            match (host, guest) {
+                (Some(host), Some(guest)) => {
+                    first.dispatched = true;
+                    pairings.push((host, guest));
+                }
+                (Some(host), None) => {
+                    first.winner_chain_id = Some(host.chain_id.clone());
+                    first.completed = true;
+                    pairings.extend(self.advance(1, slot as u32, host));
+                }
+                _ => {}
+            }
+        }
+        pairings
+    }
+
+    /// Generates the fixture list with the circle method: every player meets every other once,
+    /// one fixture per player per round, with hosting alternating. Odd counts get a bye a round.
+    fn schedule_round_robin(&mut self, players: Vec<PlayerInfo>) {
+        self.players = players;
+        self.status = TournamentStatus::InProgress;
+        let mut circle: Vec<Option<PlayerInfo>> = self.players.iter().cloned().map(Some).collect();
+        if circle.len() % 2 == 1 {
+            circle.push(None);
+        }
+        let size = circle.len();
+        self.matches = Vec::new();
+        for round in 1..size as u32 {
+            let mut slot = 0;
+            for i in 0..size / 2 {
+                let (Some(a), Some(b)) = (circle[i].clone(), circle[size - 1 - i].clone()) else {
+                    continue;
+                };
+                let (host, guest) = if (round as usize + i).is_multiple_of(2) {
+                    (a, b)
+                } else {
+                    (b, a)
+                };
+                let mut fixture = TournamentMatch::new(round, slot);
+                fixture.host = Some(host);
+                fixture.guest = Some(guest);
+                self.matches.push(fixture);
+                slot += 1;
+            }
+            circle[1..].rotate_right(1);
+        }
+    }
+
+    /// Pairs the next Swiss round: players are ranked by points (seed order breaks ties) and each
+    /// is paired with the best-ranked player below them they have not met yet. With an odd count
+    /// the lowest-ranked player without a bye sits out and scores a win.
+    fn pair_swiss_round(&mut self) -> Vec<Pairing> {
+        let round = self.current_round() + 1;
+        let points: BTreeMap<String, u32> = self
+            .standings()
+            .into_iter()
+            .map(|s| (s.chain_id, s.points))
+            .collect();
+        let mut ranked = self.players.clone();
+        ranked.sort_by_key(|p| std::cmp::Reverse(points.get(&p.chain_id).copied()));
+        let mut slot = 0;
+        if ranked.len() % 2 == 1 {
+            let had_bye = |p: &PlayerInfo| {
+                self.matches.iter().any(|m| {
+                    m.guest.is_none() && m.host.as_ref().is_some_and(|h| h.chain_id == p.chain_id)
+                })
+            };
+            let position = ranked.iter().rposition(|p| !had_bye(p)).unwrap_or(ranked.len() - 1);
+            let player = ranked.remove(position);
+            let mut bye = TournamentMatch::new(round, slot);
+            bye.winner_chain_id = Some(player.chain_id.clone());
+            bye.host = Some(player);
+            bye.dispatched = true;
+            bye.completed = true;
+            self.matches.push(bye);
+            slot += 1;
+        }
+        let pairs = self.pair_without_repeats(&ranked).unwrap_or_else(|| {
+            ranked
+                .chunks(2)
+                .map(|pair| (pair[0].clone(), pair[1].clone()))
+                .collect()
+        });
+        let mut pairings = Vec::with_capacity(pairs.len());
+        for (a, b) in pairs {
+            let hosted = |p: &PlayerInfo| {
+                self.matches
+                    .iter()
+                    .filter(|m| {
+                        m.guest.is_some()
+                            && m.host.as_ref().is_some_and(|h| h.chain_id == p.chain_id)
+                    })
+                    .count()
+            };
+            let (host, guest) = if hosted(&b) < hosted(&a) { (b, a) } else { (a, b) };
+            let mut pairing = TournamentMatch::new(round, slot);
+            pairing.host = Some(host.clone());
+            pairing.guest = Some(guest.clone());
+            pairing.dispatched = true;
+            self.matches.push(pairing);
+            pairings.push((host, guest));
+            slot += 1;
+        }
+        pairings
+    }
+
+    /// Pairs `ranked` players top-down so nobody meets a previous opponent, backtracking when a
+    /// choice leaves the rest unpairable. `None` if no such pairing exists.
+    fn pair_without_repeats(&self, ranked: &[PlayerInfo]) -> Option<Vec<Pairing>> {
+        let Some((first, rest)) = ranked.split_first() else {
+            return Some(Vec::new());
+        };
+        for (i, opponent) in rest.iter().enumerate() {
+            let have_met = self
+                .matches
+                .iter()
+                .filter_map(|m| m.opponent_of(&first.chain_id))
+                .any(|p| p.chain_id == opponent.chain_id);
+            if have_met {
+                continue;
+            }
+            let mut remaining = rest.to_vec();
+            remaining.remove(i);
+            if let Some(mut pairs) = self.pair_without_repeats(&remaining) {
+                pairs.insert(0, (first.clone(), opponent.clone()));
+                return Some(pairs);
+            }
+        }
+        None
+    }
+
+    /// Marks the league fixtures of `round` that were not started yet as dispatched and returns
+    /// them.
+    pub fn play_fixtures(&mut self, round: u32) -> Vec<Pairing> {
+        if self.kind != TournamentKind::RoundRobin || self.status != TournamentStatus::InProgress {
+            return Vec::new();
+        }
+        self.matches
+            .iter_mut()
+            .filter(|m| m.round == round && !m.dispatched)
+            .filter_map(|m| {
+                m.dispatched = true;
+                Some((m.host.clone()?, m.guest.clone()?))
+            })
+            .collect()
+    }
+
+    /// The standings table, best first: points, then head-to-head points among players level on
+    /// points (Buchholz in a Swiss tournament), then round difference and rounds won.
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut table: Vec<Standing> = self
+            .players
+            .iter()
+            .map(|p| Standing {
+                chain_id: p.chain_id.clone(),
+                name: p.name.clone(),
+                played: 0,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+                points: 0,
+                rounds_won: 0,
+                rounds_lost: 0,
+                round_difference: 0,
+                head_to_head_points: 0,
+                buchholz: 0,
+            })
+            .collect();
+        for standing in &mut table {
+            for m in &self.matches {
+                let Some(points) = m.points_of(&standing.chain_id) else {
+                    continue;
+                };
+                let is_host = m.host.as_ref().is_some_and(|h| h.chain_id == standing.chain_id);
+                let (won, lost) = if is_host {
+                    (m.host_score, m.guest_score)
+                } else {
+                    (m.guest_score, m.host_score)
+                };
+                standing.played += 1;
+                standing.points += points;
+                match points {
+                    LEAGUE_WIN_POINTS => standing.wins += 1,
+                    LEAGUE_DRAW_POINTS => standing.draws += 1,
+                    _ => standing.losses += 1,
+                }
+                standing.rounds_won += u32::from(won);
+                standing.rounds_lost += u32::from(lost);
+            }
+            standing.round_difference = standing.rounds_won as i32 - standing.rounds_lost as i32;
+        }
+        let points: BTreeMap<String, u32> =
+            table.iter().map(|s| (s.chain_id.clone(), s.points)).collect();
+        for standing in &mut table {
+            standing.head_to_head_points = self
+                .matches
+                .iter()
+                .filter(|m| {
+                    [&m.host, &m.guest].iter().flat_map(|p| p.as_ref()).any(|p| {
+                        p.chain_id != standing.chain_id
+                            && points.get(&p.chain_id) == Some(&standing.points)
+                    })
+                })
+                .filter_map(|m| m.points_of(&standing.chain_id))
+                .sum();
+            standing.buchholz = self
+                .matches
+                .iter()
+                .filter(|m| m.completed)
+                .filter_map(|m| m.opponent_of(&standing.chain_id))
+                .filter_map(|opponent| points.get(&opponent.chain_id))
+                .sum();
+        }
+        let swiss = self.kind == TournamentKind::Swiss;
+        table.sort_by(|a, b| {
+            let first_tiebreak = |s: &Standing| {
+                if swiss {
+                    s.buchholz
+                } else {
+                    s.head_to_head_points
+                }
+            };
+            b.points
+                .cmp(&a.points)
+                .then(first_tiebreak(b).cmp(&first_tiebreak(a)))
+                .then(b.head_to_head_points.cmp(&a.head_to_head_points))
+                .then(b.round_difference.cmp(&a.round_difference))
+                .then(b.rounds_won.cmp(&a.rounds_won))
+                .then_with(|| a.chain_id.cmp(&b.chain_id))
+        });
+        table
+    }
+
+    /// Records the result of a dispatched match between `host_chain_id` and `guest_chain_id`.
+    /// In a knockout a draw is replayed and a win moves the winner on; leagues and Swiss
+    /// tournaments simply count it, and a finished Swiss round pairs the next one. Returns the
+    /// pairings to start next.
+    pub fn record_result(
+        &mut self,
+        host_chain_id: &str,
+        guest_chain_id: &str,
+        winner_chain_id: Option<&str>,
+        host_score: u8,
+        guest_score: u8,
+    ) -> Vec<Pairing> {
+        let Some(played) = self.matches.iter_mut().find(|m| {
+            m.dispatched && !m.completed && m.is_between(host_chain_id, guest_chain_id)
+        }) else {
+            return Vec::new();
+        };
+        played.host_score = host_score;
+        played.guest_score = guest_score;
+        let (Some(host), Some(guest)) = (played.host.clone(), played.guest.clone()) else {
+            return Vec::new();
+        };
+        let winner = match winner_chain_id {
+            Some(chain_id) if chain_id == host.chain_id => Some(host.clone()),
+            Some(chain_id) if chain_id == guest.chain_id => Some(guest.clone()),
+            _ => None,
+        };
+        if self.kind != TournamentKind::SingleElimination {
+            played.winner_chain_id = winner.map(|w| w.chain_id);
+            played.completed = true;
+            if !self.matches.iter().all(|m| m.completed) {
+                return Vec::new();
+            }
+            if self.kind == TournamentKind::Swiss && self.current_round() < self.swiss_rounds {
+                return self.pair_swiss_round();
+            }
+            self.champion_chain_id = self.standings().first().map(|s| s.chain_id.clone());
+            self.status = TournamentStatus::Finished;
+            return Vec::new();
+        }
+        let Some(winner) = winner else {
+            return vec![(host, guest)];
+        };
+        played.winner_chain_id = Some(winner.chain_id.clone());
+        played.completed = true;
+        let (round, slot) = (played.round, played.slot);
+        self.advance(round, slot, winner)
+    }
+
+    /// Moves `winner` of the given match into the next round, crowning a champion after the
+    /// final. Returns the next-round pairing if it is now complete.
+    fn advance(&mut self, round: u32, slot: u32, winner: PlayerInfo) -> Vec<Pairing> {
+        if round == self.rounds() {
+            self.champion_chain_id = Some(winner.chain_id);
+            self.status = TournamentStatus::Finished;
+            return Vec::new();
+        }
+        let next = self.match_mut(round + 1, slot / 2);
+        if slot.is_multiple_of(2) {
+            next.host = Some(winner);
+        } else {
+            next.guest = Some(winner);
+        }
+        match (next.host.clone(), next.guest.clone()) {
+            (Some(host), Some(guest)) => {
+                next.dispatched = true;
+                vec![(host, guest)]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A player's private view of the round in progress of one of their matches.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoundState {
+    pub my_ready: bool,
+    pub opponent_ready: bool,
+    pub my_commitment: Option<CryptoHash>,
+    pub opponent_commitment: Option<CryptoHash>,
+    pub my_choice: Option<Choice>,
+    pub opponent_choice: Option<Choice>,
+    pub opponent_forfeit: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Operation {
-    CreateMatch { host_name: String },
-    JoinMatch { host_chain_id: String, player_name: String },
+    CreateMatch {
+        host_name: String,
+        format: Option<MatchFormat>,
+        rules: Option<GameRules>,
+        round_timeout_secs: Option<u64>,
+        /// Native tokens the host puts up; a guest must match it to join.
+        stake: Option<Amount>,
+        /// Makes the match private: guests must supply this code to join.
+        room_code: Option<String>,
+    },
+    JoinMatch {
+        host_chain_id: String,
+        match_id: String,
+        player_name: String,
+        stake: Option<Amount>,
+        room_code: Option<String>,
+    },
     SearchPlayer {
-        orchestrator_chain_id: String,
+        /// Defaults to the first orchestrator pinned in the application parameters.
+        orchestrator_chain_id: Option<String>,
+        player_name: String,
+        format: Option<MatchFormat>,
+    },
+    PickAndReady {
+        match_id: String,
+        commitment: CryptoHash,
+    },
+    RevealChoice {
+        match_id: String,
+        choice: Choice,
+        salt: String,
+    },
+    /// Claims the current round, or the whole match if `claim_match` is set, after the
+    /// opponent let the round deadline pass.
+    ClaimTimeout { match_id: String, claim_match: bool },
+    /// Withdraws from the matchmaking queue of the given (or default) orchestrator.
+    CancelSearch { orchestrator_chain_id: Option<String> },
+    /// Accepts or declines a ready check before a matchmade game starts.
+    RespondReadyCheck { check_id: String, accept: bool },
+    LeaveMatch { match_id: String },
+    /// Concedes an active match: the opponent is declared the winner.
+    Resign { match_id: String },
+    /// Offers the opponent a draw, which they can take with `AcceptDraw`.
+    OfferDraw { match_id: String },
+    AcceptDraw { match_id: String },
+    /// Subscribes to the matches published by a host chain.
+    Spectate { host_chain_id: String },
+    StopSpectating { host_chain_id: String },
+    /// Opens a tournament on this chain, which must be an orchestrator.
+    CreateTournament {
+        name: String,
+        /// Defaults to a single-elimination bracket.
+        kind: Option<TournamentKind>,
+        bracket_size: u32,
+        format: Option<MatchFormat>,
+    },
+    /// Starts the league fixtures of a round that have not been played yet.
+    PlayFixtures { tournament_id: String, round: u32 },
+    /// Closes registration and seeds the bracket; also happens once the bracket is full.
+    StartTournament { tournament_id: String },
+    RegisterForTournament {
+        orchestrator_chain_id: Option<String>,
+        tournament_id: String,
         player_name: String,
     },
-    PickAndReady { choice: Choice },
-    LeaveMatch,
+    /// Lists a match waiting for a guest in an orchestrator's public lobby.
+    PublishMatch {
+        match_id: String,
+        orchestrator_chain_id: Option<String>,
+        name: String,
+        description: Option<String>,
+    },
+    /// Invites a chain to join a match waiting for a guest, bypassing its room code.
+    InvitePlayer {
+        match_id: String,
+        chain_id: String,
+        expires_in_secs: Option<u64>,
+    },
+    AcceptInvite { match_id: String, player_name: String },
+    DeclineInvite { match_id: String },
+    /// Asks the opponent of a finished match for a rematch, optionally with the roles swapped.
+    RequestRematch { match_id: String, swap_host: bool },
+    AcceptRematch { match_id: String },
+}
+
+/// Why a host refused a `JoinRequest`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum JoinError {
+    MatchNotFound,
+    NotHost,
+    NotJoinable,
+    MatchFull,
+    StakeMismatch,
+    WrongRoomCode,
+}
+
+impl std::fmt::Display for JoinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            JoinError::MatchNotFound => "Match not found",
+            JoinError::NotHost => "Only host can accept joins",
+            JoinError::NotJoinable => "Match not joinable",
+            JoinError::MatchFull => "Match full",
+            JoinError::StakeMismatch => "Stake does not match the host's stake",
+            JoinError::WrongRoomCode => "Wrong room code",
+        };
+        f.write_str(reason)
+    }
+}
+
+/// The last join request the host refused, as seen by the player who sent it.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct JoinRejection {
+    pub match_id: String,
+    pub host_chain_id: String,
+    pub reason: JoinError,
+}
+
+/// How long an invitation stays valid when the host does not say otherwise.
+pub const DEFAULT_INVITATION_TTL_SECS: u64 = 10 * 60;
+
+/// A host's invitation for a specific chain to take the guest seat of a waiting match.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct Invitation {
+    pub match_id: String,
+    pub host_chain_id: String,
+    pub host_name: String,
+    pub invitee_chain_id: String,
+    pub format: MatchFormat,
+    pub rules: RuleSetKind,
+    /// Stake the invitee must match when accepting.
+    pub stake: Amount,
+    pub expires_at_micros: u64,
+}
+
+/// A rematch offered by the opponent of a finished match, keyed by that match's ID.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct RematchRequest {
+    pub match_id: String,
+    pub requester_chain_id: String,
+    pub swap_host: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -96,42 +1143,526 @@ pub struct MatchmakingPlayer {
     pub player_name: String,
     #[serde(default)]
     pub enqueued_at_micros: u64,
+    #[serde(default)]
+    pub format: MatchFormat,
+}
+
+/// Running totals kept by an orchestrator about the matches it made.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MatchmakingStats {
+    pub matches_made: u64,
+    pub players_matched: u64,
+    /// Sum of the time each matched player spent queued.
+    pub total_wait_micros: u64,
+    /// When each match of the last hour was made, oldest first.
+    pub recent_match_times_micros: Vec<u64>,
+}
+
+impl MatchmakingStats {
+    pub const RECENT_WINDOW_MICROS: u64 = 60 * 60 * 1_000_000;
+
+    pub fn record(&mut self, now_micros: u64, waits_micros: &[u64]) {
+        self.matches_made += 1;
+        self.players_matched += waits_micros.len() as u64;
+        self.total_wait_micros = waits_micros
+            .iter()
+            .fold(self.total_wait_micros, |total, wait| total.saturating_add(*wait));
+        let cutoff_micros = now_micros.saturating_sub(Self::RECENT_WINDOW_MICROS);
+        self.recent_match_times_micros.retain(|t| *t >= cutoff_micros);
+        self.recent_match_times_micros.push(now_micros);
+    }
+
+    pub fn average_wait_micros(&self) -> Option<u64> {
+        (self.players_matched > 0).then(|| self.total_wait_micros / self.players_matched)
+    }
+
+    pub fn matches_since(&self, since_micros: u64) -> usize {
+        self.recent_match_times_micros
+            .iter()
+            .filter(|t| **t >= since_micros)
+            .count()
+    }
+}
+
+/// An open match advertised in an orchestrator's public lobby.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct LobbyListing {
+    pub match_id: String,
+    pub host_chain_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub format: MatchFormat,
+    pub rules: RuleSetKind,
+    pub stake: Amount,
+    /// Whether joining requires the room code.
+    pub private: bool,
+    pub listed_at_micros: u64,
+}
+
+/// A pairing proposed by the orchestrator, waiting for both players to accept it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingMatch {
+    pub check_id: String,
+    pub host: MatchmakingPlayer,
+    pub guest: MatchmakingPlayer,
+    pub host_accepted: bool,
+    pub guest_accepted: bool,
+    pub deadline_micros: u64,
+}
+
+/// A ready check received by a player from an orchestrator.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct ReadyCheck {
+    pub check_id: String,
+    pub orchestrator_chain_id: String,
+    pub opponent_name: String,
+    pub format: MatchFormat,
+    pub deadline_micros: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CrossChainMessage {
-    JoinRequest { player_chain_id: ChainId, player_name: String },
+    JoinRequest {
+        match_id: String,
+        player_chain_id: ChainId,
+        player_name: String,
+        /// Stake the guest already transferred to the host's escrow account.
+        stake: Amount,
+        room_code: Option<String>,
+    },
+    /// Reply to a `JoinRequest` the host refused; any stake sent along is refunded.
+    JoinRejected { match_id: String, reason: JoinError },
     InitialStateSync { game: Game },
     GameSync { game: Game },
-    ReadyNotice { player_chain_id: ChainId, round: u8 },
+    ReadyNotice {
+        match_id: String,
+        player_chain_id: ChainId,
+        round: u8,
+        commitment: CryptoHash,
+    },
     ChoiceReveal {
+        match_id: String,
         player_chain_id: ChainId,
         round: u8,
         choice: Choice,
+        salt: String,
+    },
+    /// A guest claiming the round, or the match, after the host let the deadline pass. The
+    /// host checks the claim against its own clock and round state before applying it.
+    TimeoutClaim {
+        match_id: String,
+        player_chain_id: ChainId,
+        round: u8,
+        claim_match: bool,
+    },
+    /// A guest conceding its match to the host.
+    Resignation {
+        match_id: String,
+        player_chain_id: ChainId,
+    },
+    /// A draw offer, valid only until `round` resolves.
+    DrawOffer {
+        match_id: String,
+        player_chain_id: ChainId,
+        round: u8,
+    },
+    /// A guest taking the host's draw offer.
+    DrawAccepted {
+        match_id: String,
+        player_chain_id: ChainId,
     },
-    LeaveNotice { player_chain_id: ChainId },
     MatchmakingEnqueue {
         player_chain_id: ChainId,
         player_name: String,
+        format: MatchFormat,
     },
     MatchmakingEnqueued {
         orchestrator_chain_id: ChainId,
     },
+    MatchmakingDequeue {
+        player_chain_id: ChainId,
+    },
+    /// Sent to a queued player whose entry went stale and was pruned from the queue.
+    MatchmakingExpired {
+        orchestrator_chain_id: ChainId,
+    },
+    /// Orchestrator asks both paired players to confirm before the game is created.
+    ReadyCheckRequest {
+        check_id: String,
+        opponent_name: String,
+        format: MatchFormat,
+        deadline_micros: u64,
+    },
+    ReadyCheckResponse {
+        check_id: String,
+        player_chain_id: ChainId,
+        accept: bool,
+    },
+    /// Host adds a waiting match to the orchestrator's lobby.
+    LobbyListed { listing: LobbyListing },
+    /// Host withdraws a match from the lobby once it filled or was abandoned.
+    LobbyDelisted { match_id: String },
+    /// The ready check was declined or timed out; no game will start.
+    ReadyCheckCancelled { check_id: String },
     MatchmakingStart {
         host_name: String,
         guest_chain_id: ChainId,
         guest_name: String,
+        format: MatchFormat,
+        tournament_id: Option<String>,
     },
     MatchmakingFound {
         host_chain_id: ChainId,
     },
+    /// Final result of a match, reported by its host to the orchestrator that paired it.
+    MatchResult {
+        match_id: String,
+        host_chain_id: ChainId,
+        guest_chain_id: ChainId,
+        winner_chain_id: Option<ChainId>,
+        host_score: u8,
+        guest_score: u8,
+        tournament_id: Option<String>,
+    },
+    TournamentRegister {
+        tournament_id: String,
+        player_chain_id: ChainId,
+        player_name: String,
+    },
+    TournamentRegistered { tournament_id: String },
+    MatchInvite { invitation: Invitation },
+    InviteDeclined {
+        match_id: String,
+        player_chain_id: ChainId,
+    },
+    RematchRequest {
+        match_id: String,
+        player_chain_id: ChainId,
+        swap_host: bool,
+    },
+    /// Tells the requester its rematch was accepted; sent only when the requester will host.
+    RematchAccepted {
+        match_id: String,
+        player_chain_id: ChainId,
+        swap_host: bool,
+    },
+}
+
+/// Preimage of a choice commitment. The match, round and player are part of the hash so a
+/// commitment can neither be replayed in a later round nor copied by the opponent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChoiceCommitment {
+    pub match_id: String,
+    pub round: u8,
+    pub player_chain_id: String,
+    pub choice: Choice,
+    pub salt: String,
 }
 
-pub fn round_outcome(host_choice: Choice, guest_choice: Choice) -> RoundOutcome {
-    use Choice::*;
-    match (host_choice, guest_choice) {
-        (a, b) if a == b => RoundOutcome::Draw,
-        (Stone, Scissors) | (Paper, Stone) | (Scissors, Paper) => RoundOutcome::HostWins,
-        _ => RoundOutcome::GuestWins,
+impl BcsHashable<'_> for ChoiceCommitment {}
+
+pub fn choice_commitment(
+    match_id: &str,
+    round: u8,
+    player_chain_id: &str,
+    choice: Choice,
+    salt: &str,
+) -> CryptoHash {
+    CryptoHash::new(&ChoiceCommitment {
+        match_id: match_id.to_string(),
+        round,
+        player_chain_id: player_chain_id.to_string(),
+        choice,
+        salt: salt.to_string(),
+    })
+}
+
+/// Preimage of a room code hash; the match ID keeps equal codes from hashing alike.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomCode {
+    pub match_id: String,
+    pub code: String,
+}
+
+impl BcsHashable<'_> for RoomCode {}
+
+pub fn room_code_hash(match_id: &str, code: &str) -> CryptoHash {
+    CryptoHash::new(&RoomCode {
+        match_id: match_id.to_string(),
+        code: code.to_string(),
+    })
+}
+
+pub fn round_outcome(rules: &GameRules, host_choice: Choice, guest_choice: Choice) -> RoundOutcome {
+    rules.outcome(host_choice, guest_choice)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    #[test]
+    fn best_of_ends_on_majority_or_after_all_rounds() {
+        let format = MatchFormat {
+            kind: MatchFormatKind::BestOf,
+            rounds: 5,
+        };
+        assert_eq!(format.result(2, 0, 2), None);
+        assert_eq!(format.result(3, 0, 3), Some(RoundOutcome::HostWins));
+        assert_eq!(format.result(1, 3, 4), Some(RoundOutcome::GuestWins));
+        assert_eq!(format.result(2, 2, 4), None);
+        assert_eq!(format.result(2, 2, 5), Some(RoundOutcome::Draw));
+    }
+
+    #[test]
+    fn fixed_rounds_play_every_round() {
+        let format = MatchFormat {
+            kind: MatchFormatKind::FixedRounds,
+            rounds: 3,
+        };
+        assert_eq!(format.result(2, 0, 2), None);
+        assert_eq!(format.result(2, 1, 3), Some(RoundOutcome::HostWins));
+        assert_eq!(format.result(1, 1, 3), Some(RoundOutcome::Draw));
+    }
+
+    #[test]
+    fn lizard_spock_outcomes() {
+        let rules = GameRules::lizard_spock();
+        let index = |name: &str| Choice(rules.moves.iter().position(|m| m == name).unwrap() as u8);
+        let wins = [
+            ("Scissors", "Paper"),
+            ("Paper", "Rock"),
+            ("Rock", "Lizard"),
+            ("Lizard", "Spock"),
+            ("Spock", "Scissors"),
+            ("Scissors", "Lizard"),
+            ("Lizard", "Paper"),
+            ("Paper", "Spock"),
+            ("Spock", "Rock"),
+            ("Rock", "Scissors"),
+        ];
+        for (winner, loser) in wins {
+            assert_eq!(rules.outcome(index(winner), index(loser)), RoundOutcome::HostWins);
+            assert_eq!(rules.outcome(index(loser), index(winner)), RoundOutcome::GuestWins);
+        }
+        for name in &rules.moves {
+            assert_eq!(rules.outcome(index(name), index(name)), RoundOutcome::Draw);
+        }
+    }
+
+    #[test]
+    fn custom_rules_reject_asymmetric_payoffs() {
+        let rules = |payoffs: Vec<i8>| GameRules {
+            kind: RuleSetKind::Custom,
+            moves: ["A", "B"].map(String::from).to_vec(),
+            payoffs,
+        };
+        assert!(rules(vec![0, 1, -1, 0]).normalized().is_some());
+        assert!(rules(vec![0, 1, 1, 0]).normalized().is_none());
+        assert!(rules(vec![0, 2, -2, 0]).normalized().is_none());
+        assert!(rules(vec![1, 0, 0, 0]).normalized().is_none());
+        assert!(rules(vec![0, 1, -1]).normalized().is_none());
+    }
+
+    #[test]
+    fn elo_is_zero_sum_for_equal_ratings() {
+        for rating in [INITIAL_RATING, 800, 2400] {
+            let winner = elo_rating(rating, rating, GameResult::Win);
+            let loser = elo_rating(rating, rating, GameResult::Loss);
+            assert_eq!(winner - rating, rating - loser);
+            assert_eq!(winner - rating, (ELO_K_FACTOR / 2.0) as i32);
+            assert_eq!(elo_rating(rating, rating, GameResult::Draw), rating);
+        }
+    }
+
+    fn players(count: usize) -> Vec<PlayerInfo> {
+        (1..=count)
+            .map(|seed| PlayerInfo {
+                chain_id: format!("chain-{seed}"),
+                name: format!("player-{seed}"),
+            })
+            .collect()
+    }
+
+    fn chain_ids(pairings: &[Pairing]) -> Vec<(&str, &str)> {
+        pairings
+            .iter()
+            .map(|(host, guest)| (host.chain_id.as_str(), guest.chain_id.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn knockout_gives_top_seeds_the_byes() {
+        let mut tournament = Tournament::new(
+            "t".to_string(),
+            "Cup".to_string(),
+            TournamentKind::SingleElimination,
+            MatchFormat::default(),
+            8,
+        );
+        let pairings = tournament.start(players(5));
+        let byes: Vec<&str> = tournament
+            .matches
+            .iter()
+            .filter(|m| m.round == 1 && m.guest.is_none())
+            .map(|m| {
+                assert!(m.completed);
+                m.winner_chain_id.as_deref().unwrap()
+            })
+            .collect();
+        assert_eq!(byes, ["chain-1", "chain-2", "chain-3"]);
+        // Seeds 2 and 3 both had byes, so their semi-final starts right away.
+        assert_eq!(
+            chain_ids(&pairings),
+            [("chain-4", "chain-5"), ("chain-2", "chain-3")]
+        );
+
+        // A draw is replayed; the winner meets the top seed.
+        let replay = tournament.record_result("chain-4", "chain-5", None, 1, 1);
+        assert_eq!(chain_ids(&replay), [("chain-4", "chain-5")]);
+        let next = tournament.record_result("chain-4", "chain-5", Some("chain-5"), 1, 2);
+        assert_eq!(chain_ids(&next), [("chain-1", "chain-5")]);
+        assert!(tournament
+            .record_result("chain-2", "chain-3", Some("chain-2"), 2, 0)
+            .is_empty());
+        let final_match = tournament.record_result("chain-1", "chain-5", Some("chain-1"), 2, 1);
+        assert_eq!(chain_ids(&final_match), [("chain-1", "chain-2")]);
+        tournament.record_result("chain-1", "chain-2", Some("chain-2"), 0, 2);
+        assert_eq!(tournament.status, TournamentStatus::Finished);
+        assert_eq!(tournament.champion_chain_id.as_deref(), Some("chain-2"));
+    }
+
+    #[test]
+    fn round_robin_pairs_everyone_exactly_once() {
+        for count in 2..=9 {
+            let mut tournament = Tournament::new(
+                "t".to_string(),
+                "League".to_string(),
+                TournamentKind::RoundRobin,
+                MatchFormat::default(),
+                MAX_BRACKET_SIZE,
+            );
+            assert!(tournament.start(players(count)).is_empty());
+            let mut met = BTreeSet::new();
+            for round in 1..=tournament.current_round() {
+                let pairings = tournament.play_fixtures(round);
+                let mut busy = BTreeSet::new();
+                for (host, guest) in chain_ids(&pairings) {
+                    assert!(busy.insert(host) && busy.insert(guest));
+                    assert!(met.insert((host.min(guest).to_string(), host.max(guest).to_string())));
+                }
+                for (host, guest) in chain_ids(&pairings) {
+                    tournament.record_result(host, guest, Some(host), 2, 0);
+                }
+            }
+            assert_eq!(met.len(), count * (count - 1) / 2);
+            assert_eq!(tournament.status, TournamentStatus::Finished);
+        }
+    }
+
+    #[test]
+    fn round_robin_breaks_ties_on_head_to_head() {
+        let mut tournament = Tournament::new(
+            "t".to_string(),
+            "League".to_string(),
+            TournamentKind::RoundRobin,
+            MatchFormat::default(),
+            4,
+        );
+        tournament.start(players(4));
+        // Winner first, with the winner's score. Players 1 and 2 finish on six points; 2 has the
+        // better round difference but lost to 1.
+        let results = [
+            ("chain-1", "chain-2", Some((2, 1))),
+            ("chain-3", "chain-1", Some((2, 0))),
+            ("chain-1", "chain-4", Some((2, 1))),
+            ("chain-2", "chain-3", Some((2, 0))),
+            ("chain-2", "chain-4", Some((2, 0))),
+            ("chain-3", "chain-4", None),
+        ];
+        for round in 1..=3 {
+            for (host, guest) in tournament.play_fixtures(round) {
+                let (host, guest) = (host.chain_id, guest.chain_id);
+                let (winner, loser, score) = results
+                    .iter()
+                    .find(|(a, b, _)| (*a, *b) == (&host, &guest) || (*b, *a) == (&host, &guest))
+                    .unwrap();
+                let (winner, (won, lost)) = match score {
+                    Some(score) => (Some(*winner), *score),
+                    None => (None, (1, 1)),
+                };
+                let (host_score, guest_score) = if host == *loser {
+                    (lost, won)
+                } else {
+                    (won, lost)
+                };
+                tournament.record_result(&host, &guest, winner, host_score, guest_score);
+            }
+        }
+        let standings = tournament.standings();
+        let table: Vec<(&str, u32)> = standings
+            .iter()
+            .map(|s| (s.chain_id.as_str(), s.points))
+            .collect();
+        assert_eq!(
+            table,
+            [("chain-1", 6), ("chain-2", 6), ("chain-3", 4), ("chain-4", 1)]
+        );
+        assert!(standings[1].round_difference > standings[0].round_difference);
+        assert_eq!(standings[0].head_to_head_points, LEAGUE_WIN_POINTS);
+        assert_eq!(tournament.champion_chain_id.as_deref(), Some("chain-1"));
+    }
+
+    #[test]
+    fn swiss_avoids_rematches_and_repeat_byes() {
+        for count in 2..=16 {
+            let mut tournament = Tournament::new(
+                "t".to_string(),
+                "Open".to_string(),
+                TournamentKind::Swiss,
+                MatchFormat::default(),
+                MAX_BRACKET_SIZE,
+            );
+            let mut pairings = tournament.start(players(count));
+            let mut met = BTreeSet::new();
+            while !pairings.is_empty() {
+                let mut next = Vec::new();
+                for (host, guest) in chain_ids(&pairings) {
+                    assert!(met.insert((host.min(guest).to_string(), host.max(guest).to_string())));
+                    // The better seed always wins.
+                    let seed = |chain_id: &str| chain_id["chain-".len()..].parse::<u32>().unwrap();
+                    let winner = if seed(host) < seed(guest) { host } else { guest };
+                    next.extend(tournament.record_result(host, guest, Some(winner), 2, 0));
+                }
+                pairings = next;
+            }
+            assert_eq!(tournament.status, TournamentStatus::Finished);
+            assert_eq!(tournament.current_round(), tournament.swiss_rounds);
+            let byes: Vec<&str> = tournament
+                .matches
+                .iter()
+                .filter(|m| m.guest.is_none())
+                .map(|m| m.host.as_ref().unwrap().chain_id.as_str())
+                .collect();
+            let expected_byes = if count % 2 == 1 { tournament.swiss_rounds } else { 0 };
+            assert_eq!(byes.len(), expected_byes as usize);
+            assert_eq!(byes.iter().collect::<BTreeSet<_>>().len(), byes.len());
+            assert_eq!(tournament.champion_chain_id.as_deref(), Some("chain-1"));
+
+            let standings = tournament.standings();
+            for standing in &standings {
+                let opponents_points: u32 = tournament
+                    .matches
+                    .iter()
+                    .filter_map(|m| m.opponent_of(&standing.chain_id))
+                    .map(|o| standings.iter().find(|s| s.chain_id == o.chain_id).unwrap().points)
+                    .sum();
+                assert_eq!(standing.buchholz, opponents_points);
+            }
+        }
     }
 }
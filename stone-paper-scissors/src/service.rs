@@ -2,18 +2,27 @@
 
 mod state;
 
-use std::sync::Arc;
-
-use async_graphql::{EmptySubscription, Object, Request, Response, Schema};
-use linera_sdk::{linera_base_types::WithServiceAbi, views::View, Service, ServiceRuntime};
-use stone_paper_scissors::{Choice, Game, MatchStatus, Operation, RoundRecord, SpsAbi, RoundOutcome, SpsParameters};
+use std::{collections::BTreeMap, sync::Arc};
+
+use async_graphql::{EmptySubscription, Enum, Object, Request, Response, Schema, SimpleObject};
+use linera_sdk::{
+    linera_base_types::{Amount, CryptoHash, WithServiceAbi},
+    views::View,
+    Service, ServiceRuntime,
+};
+use stone_paper_scissors::{
+    choice_commitment, Choice, Game, GameRules, Invitation, JoinRejection, LobbyListing,
+    MatchFormat, MatchStatus, MatchmakingPlayer, MatchmakingStats, Operation, PlayerRating,
+    RatingChange, ReadyCheck, RematchRequest, RoundOutcome, RoundRecord, RoundState, SpsAbi,
+    SpsParameters, Standing, Tournament, TournamentKind, TournamentStatus,
+};
 
 use self::state::SpsState;
 
 linera_sdk::service!(SpsService);
 
 pub struct SpsService {
-    state: SpsState,
+    state: Arc<SpsState>,
     runtime: Arc<ServiceRuntime<Self>>,
 }
 
@@ -29,30 +38,146 @@ impl Service for SpsService {
             .await
             .expect("Failed to load state");
         SpsService {
-            state,
+            state: Arc::new(state),
             runtime: Arc::new(runtime),
         }
     }
 
     async fn handle_query(&self, request: Request) -> Response {
-        let game = self.state.game.get().clone();
-        let my_ready = self.state.my_ready.get().clone();
-        let opponent_ready = self.state.opponent_ready.get().clone();
-        let my_choice = self.state.my_choice.get().clone();
-        let opponent_choice = self.state.opponent_choice.get().clone();
+        let matches: BTreeMap<String, Game> = self
+            .state
+            .matches
+            .index_values()
+            .await
+            .expect("Failed to read matches")
+            .into_iter()
+            .collect();
+        let rounds: BTreeMap<String, RoundState> = self
+            .state
+            .rounds
+            .index_values()
+            .await
+            .expect("Failed to read round states")
+            .into_iter()
+            .collect();
+        let rematch_requests = self
+            .state
+            .rematch_requests
+            .index_values()
+            .await
+            .expect("Failed to read rematch requests")
+            .into_iter()
+            .map(|(_, request)| request)
+            .collect();
+        let invitations = self
+            .state
+            .invitations
+            .index_values()
+            .await
+            .expect("Failed to read invitations")
+            .into_iter()
+            .map(|(_, invitation)| invitation)
+            .collect();
+        let sent_invitations = self
+            .state
+            .sent_invitations
+            .index_values()
+            .await
+            .expect("Failed to read sent invitations")
+            .into_iter()
+            .map(|(_, invitation)| invitation)
+            .collect();
+        let ready_checks = self
+            .state
+            .ready_checks
+            .index_values()
+            .await
+            .expect("Failed to read ready checks")
+            .into_iter()
+            .map(|(_, check)| check)
+            .collect();
+        let spectating = self
+            .state
+            .spectating
+            .indices()
+            .await
+            .expect("Failed to read spectated hosts");
+        let draw_offers = self
+            .state
+            .draw_offers
+            .indices()
+            .await
+            .expect("Failed to read draw offers");
+        let spectated: BTreeMap<String, Game> = self
+            .state
+            .spectated
+            .index_values()
+            .await
+            .expect("Failed to read spectated matches")
+            .into_iter()
+            .collect();
+        let tournaments: BTreeMap<String, Tournament> = self
+            .state
+            .tournaments
+            .index_values()
+            .await
+            .expect("Failed to read tournaments")
+            .into_iter()
+            .collect();
+        let lobby = self
+            .state
+            .lobby
+            .index_values()
+            .await
+            .expect("Failed to read lobby")
+            .into_iter()
+            .map(|(_, listing)| listing)
+            .collect();
+        let matchmaking_queue = self.state.matchmaking_queue.get().clone();
+        let matchmaking_stats = self.state.matchmaking_stats.get().clone();
+        let pending_ready_checks = self
+            .state
+            .pending_matches
+            .count()
+            .await
+            .expect("Failed to count pending matches");
         let last_notification = self.state.last_notification.get().clone();
+        let last_join_rejection = self.state.last_join_rejection.get().clone();
+        let chain_id = self.runtime.chain_id().to_string();
         let schema = Schema::build(
             QueryRoot {
-                game,
-                chain_id: self.runtime.chain_id().to_string(),
-                my_ready,
-                opponent_ready,
-                my_choice,
-                opponent_choice,
+                matches: matches.clone(),
+                rounds,
+                state: self.state.clone(),
+                tournaments,
+                lobby,
+                matchmaking_queue,
+                matchmaking_stats,
+                pending_ready_checks,
+                invitations,
+                sent_invitations,
+                rematch_requests,
+                ready_checks,
+                last_join_rejection,
+                spectating,
+                spectated,
+                draw_offers,
+                chain_id: chain_id.clone(),
                 last_notification,
+                default_format: self.runtime.application_parameters().default_format,
+                orchestrator_chain_ids: self
+                    .runtime
+                    .application_parameters()
+                    .orchestrator_chain_ids
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect(),
+                now_micros: self.runtime.system_time().micros(),
             },
             MutationRoot {
                 runtime: self.runtime.clone(),
+                matches,
+                chain_id,
             },
             EmptySubscription,
         )
@@ -62,62 +187,216 @@ impl Service for SpsService {
 }
 
 struct QueryRoot {
-    game: Option<Game>,
+    matches: BTreeMap<String, Game>,
+    rounds: BTreeMap<String, RoundState>,
+    state: Arc<SpsState>,
+    tournaments: BTreeMap<String, Tournament>,
+    lobby: Vec<LobbyListing>,
+    matchmaking_queue: Vec<MatchmakingPlayer>,
+    matchmaking_stats: MatchmakingStats,
+    pending_ready_checks: usize,
+    invitations: Vec<Invitation>,
+    sent_invitations: Vec<Invitation>,
+    rematch_requests: Vec<RematchRequest>,
+    ready_checks: Vec<ReadyCheck>,
+    last_join_rejection: Option<JoinRejection>,
+    spectating: Vec<String>,
+    spectated: BTreeMap<String, Game>,
+    draw_offers: Vec<String>,
     chain_id: String,
-    my_ready: bool,
-    opponent_ready: bool,
-    my_choice: Option<Choice>,
-    opponent_choice: Option<Choice>,
     last_notification: Option<String>,
+    default_format: MatchFormat,
+    orchestrator_chain_ids: Vec<String>,
+    now_micros: u64,
+}
+
+impl QueryRoot {
+    fn round_state(&self, match_id: &str) -> RoundState {
+        self.rounds.get(match_id).cloned().unwrap_or_default()
+    }
+
+    /// Every player rated by this orchestrator chain, without their rating history.
+    async fn player_ratings(&self) -> Vec<PlayerRating> {
+        self.state
+            .ratings
+            .index_values()
+            .await
+            .expect("Failed to read ratings")
+            .into_iter()
+            .map(|(_, rating)| rating)
+            .collect()
+    }
+
+    /// Looks up a single archived match through the archive index.
+    async fn archived(&self, match_id: &str) -> Option<Game> {
+        let position = self
+            .state
+            .archive_index
+            .get(match_id)
+            .await
+            .expect("Failed to read archive index")?;
+        self.state
+            .archive
+            .get(position)
+            .await
+            .expect("Failed to read match archive")
+    }
 }
 
 #[Object]
 impl QueryRoot {
-    async fn game(&self) -> Option<&Game> {
-        self.game.as_ref()
+    /// All matches this chain takes part in.
+    async fn matches(&self) -> Vec<&Game> {
+        self.matches.values().collect()
     }
 
-    async fn match_status(&self) -> Option<MatchStatus> {
-        self.game.as_ref().map(|g| g.status)
+    /// Matches that have not ended yet. Finished matches are moved to the archive, so these
+    /// are all the live ones.
+    async fn active_matches(&self) -> Vec<&Game> {
+        self.matches.values().collect()
     }
 
-    async fn round(&self) -> Option<i32> {
-        self.game.as_ref().map(|g| g.round as i32)
+    /// A live match, or an archived one if it is over.
+    async fn game(&self, match_id: String) -> Option<Game> {
+        match self.matches.get(&match_id) {
+            Some(game) => Some(game.clone()),
+            None => self.archived(&match_id).await,
+        }
+    }
+
+    /// Past matches of this chain, newest first, optionally only those against one opponent.
+    /// Only the requested page is read from the archive.
+    async fn archived_matches(
+        &self,
+        opponent_chain_id: Option<String>,
+        #[graphql(default = 0)] offset: usize,
+        #[graphql(default = 20)] limit: usize,
+    ) -> ArchivePage {
+        let Some(opponent_chain_id) = opponent_chain_id else {
+            let total_count = self.state.archive.count();
+            let end = total_count.saturating_sub(offset);
+            let start = end.saturating_sub(limit);
+            let mut matches = self
+                .state
+                .archive
+                .read(start..end)
+                .await
+                .expect("Failed to read match archive");
+            matches.reverse();
+            return ArchivePage {
+                total_count,
+                matches,
+            };
+        };
+        let positions = self
+            .state
+            .archive_by_opponent
+            .get(&opponent_chain_id)
+            .await
+            .expect("Failed to read archive index")
+            .unwrap_or_default();
+        let mut matches = Vec::new();
+        for position in positions.iter().rev().skip(offset).take(limit) {
+            if let Some(game) = self
+                .state
+                .archive
+                .get(*position)
+                .await
+                .expect("Failed to read match archive")
+            {
+                matches.push(game);
+            }
+        }
+        ArchivePage {
+            total_count: positions.len(),
+            matches,
+        }
+    }
+
+    async fn archived_match(&self, match_id: String) -> Option<Game> {
+        self.archived(&match_id).await
+    }
+
+    async fn match_status(&self, match_id: String) -> Option<MatchStatus> {
+        self.matches.get(&match_id).map(|g| g.status)
+    }
+
+    async fn match_format(&self, match_id: String) -> Option<MatchFormat> {
+        self.matches.get(&match_id).map(|g| g.format)
     }
 
-    async fn is_host(&self) -> bool {
-        self.game
-            .as_ref()
+    async fn default_match_format(&self) -> MatchFormat {
+        self.default_format
+    }
+
+    /// Orchestrators pinned in the application parameters; empty when any chain may match.
+    async fn orchestrator_chain_ids(&self) -> &[String] {
+        &self.orchestrator_chain_ids
+    }
+
+    async fn rules(&self, match_id: String) -> Option<&GameRules> {
+        self.matches.get(&match_id).map(|g| &g.rules)
+    }
+
+    /// Built-in rule sets that can be passed to `createMatch`. A move is sent and returned as
+    /// its index in `moves`, so with the classic rules `0` is Stone; `pickAndReady` and
+    /// `revealChoice` take that index.
+    async fn rule_presets(&self) -> Vec<GameRules> {
+        vec![GameRules::classic(), GameRules::lizard_spock()]
+    }
+
+    async fn round(&self, match_id: String) -> Option<i32> {
+        self.matches.get(&match_id).map(|g| g.round as i32)
+    }
+
+    async fn is_host(&self, match_id: String) -> bool {
+        self.matches
+            .get(&match_id)
             .map(|g| g.host_chain_id == self.chain_id)
             .unwrap_or(false)
     }
 
-    async fn opponent_chain_id(&self) -> Option<String> {
-        let game = self.game.as_ref()?;
+    async fn opponent_chain_id(&self, match_id: String) -> Option<String> {
+        let game = self.matches.get(&match_id)?;
         game.players
             .iter()
             .find(|p| p.chain_id != self.chain_id)
             .map(|p| p.chain_id.clone())
     }
 
-    async fn my_ready(&self) -> bool {
-        self.my_ready
+    async fn round_deadline_passed(&self, match_id: String) -> bool {
+        self.matches
+            .get(&match_id)
+            .and_then(|g| g.round_deadline_micros)
+            .is_some_and(|deadline| self.now_micros > deadline)
     }
 
-    async fn opponent_ready(&self) -> bool {
-        self.opponent_ready
+    async fn my_ready(&self, match_id: String) -> bool {
+        self.round_state(&match_id).my_ready
     }
 
-    async fn my_choice(&self) -> Option<Choice> {
-        self.my_choice
+    async fn opponent_ready(&self, match_id: String) -> bool {
+        self.round_state(&match_id).opponent_ready
     }
 
-    async fn opponent_choice(&self) -> Option<Choice> {
-        self.opponent_choice
+    async fn my_commitment(&self, match_id: String) -> Option<CryptoHash> {
+        self.round_state(&match_id).my_commitment
     }
 
-    async fn my_score(&self) -> Option<i32> {
-        let game = self.game.as_ref()?;
+    async fn opponent_commitment(&self, match_id: String) -> Option<CryptoHash> {
+        self.round_state(&match_id).opponent_commitment
+    }
+
+    async fn my_choice(&self, match_id: String) -> Option<Choice> {
+        self.round_state(&match_id).my_choice
+    }
+
+    async fn opponent_choice(&self, match_id: String) -> Option<Choice> {
+        self.round_state(&match_id).opponent_choice
+    }
+
+    async fn my_score(&self, match_id: String) -> Option<i32> {
+        let game = self.matches.get(&match_id)?;
         if game.host_chain_id == self.chain_id {
             Some(game.host_score as i32)
         } else {
@@ -125,8 +404,8 @@ impl QueryRoot {
         }
     }
 
-    async fn opponent_score(&self) -> Option<i32> {
-        let game = self.game.as_ref()?;
+    async fn opponent_score(&self, match_id: String) -> Option<i32> {
+        let game = self.matches.get(&match_id)?;
         if game.host_chain_id == self.chain_id {
             Some(game.guest_score as i32)
         } else {
@@ -134,64 +413,541 @@ impl QueryRoot {
         }
     }
 
-    async fn last_outcome(&self) -> Option<RoundOutcome> {
-        self.game.as_ref().and_then(|g| g.last_outcome)
+    async fn last_outcome(&self, match_id: String) -> Option<RoundOutcome> {
+        self.matches.get(&match_id).and_then(|g| g.last_outcome)
     }
 
-    async fn round_history(&self) -> Vec<RoundRecord> {
-        self.game
-            .as_ref()
+    async fn round_history(&self, match_id: String) -> Vec<RoundRecord> {
+        self.matches
+            .get(&match_id)
             .map(|g| g.history.clone())
             .unwrap_or_default()
     }
 
-    async fn last_round_record(&self) -> Option<RoundRecord> {
-        self.game
-            .as_ref()
+    async fn last_round_record(&self, match_id: String) -> Option<RoundRecord> {
+        self.matches
+            .get(&match_id)
             .and_then(|g| g.history.last().cloned())
     }
 
+    /// Invitations to join other hosts' matches that have not expired yet.
+    async fn pending_invitations(&self) -> Vec<&Invitation> {
+        self.invitations
+            .iter()
+            .filter(|i| i.expires_at_micros >= self.now_micros)
+            .collect()
+    }
+
+    /// Invitations this chain sent as a host and that were neither accepted nor declined.
+    async fn sent_invitations(&self) -> &[Invitation] {
+        &self.sent_invitations
+    }
+
+    /// Matchmaking ready checks waiting for this chain's answer whose window is still open.
+    async fn ready_checks(&self) -> Vec<&ReadyCheck> {
+        self.ready_checks
+            .iter()
+            .filter(|check| check.deadline_micros >= self.now_micros)
+            .collect()
+    }
+
+    /// Rematches opponents have offered this chain.
+    async fn rematch_requests(&self) -> &[RematchRequest] {
+        &self.rematch_requests
+    }
+
+    /// Matches in which the opponent offered this chain a draw.
+    async fn draw_offers(&self) -> &[String] {
+        &self.draw_offers
+    }
+
+    /// Host chains this chain follows as a spectator.
+    async fn spectating(&self) -> &[String] {
+        &self.spectating
+    }
+
+    /// Read-only view of matches followed as a spectator, optionally from one host only.
+    async fn spectated_matches(&self, host_chain_id: Option<String>) -> Vec<&Game> {
+        self.spectated
+            .values()
+            .filter(|g| host_chain_id.as_ref().is_none_or(|h| g.host_chain_id == *h))
+            .collect()
+    }
+
+    async fn spectated_match(&self, match_id: String) -> Option<&Game> {
+        self.spectated.get(&match_id)
+    }
+
+    /// Elo ratings of every player rated by this orchestrator chain, highest first.
+    async fn ratings(&self) -> Vec<PlayerRating> {
+        let mut ratings = self.player_ratings().await;
+        ratings.sort_by_key(|r| std::cmp::Reverse(r.rating));
+        ratings
+    }
+
+    /// A single player's rating, including their recent rating history.
+    async fn rating(&self, chain_id: String) -> Option<RatingDetails> {
+        let rating = self
+            .state
+            .ratings
+            .get(&chain_id)
+            .await
+            .expect("Failed to read rating")?;
+        let history = self
+            .state
+            .rating_history
+            .get(&chain_id)
+            .await
+            .expect("Failed to read rating history")
+            .unwrap_or_default();
+        Some(RatingDetails { rating, history })
+    }
+
+    /// A page of the leaderboard of players rated by this orchestrator chain.
+    async fn leaderboard(
+        &self,
+        #[graphql(default_with = "LeaderboardSort::Rating")] sort_by: LeaderboardSort,
+        #[graphql(default = 0)] offset: usize,
+        #[graphql(default = 20)] limit: usize,
+    ) -> LeaderboardPage {
+        let mut players = self.player_ratings().await;
+        players.sort_by(|a, b| {
+            let order = match sort_by {
+                LeaderboardSort::Rating => a.rating.cmp(&b.rating),
+                LeaderboardSort::Wins => a.wins.cmp(&b.wins),
+                LeaderboardSort::WinRate => a.win_rate().total_cmp(&b.win_rate()),
+                LeaderboardSort::CurrentStreak => a.current_streak.cmp(&b.current_streak),
+                LeaderboardSort::GamesPlayed => a.games_played.cmp(&b.games_played),
+            };
+            order
+                .reverse()
+                .then_with(|| b.rating.cmp(&a.rating))
+                .then_with(|| a.chain_id.cmp(&b.chain_id))
+        });
+        let total_count = players.len();
+        let entries = players
+            .into_iter()
+            .enumerate()
+            .skip(offset)
+            .take(limit)
+            .map(|(index, player)| LeaderboardEntry {
+                rank: index + 1,
+                win_rate: player.win_rate(),
+                chain_id: player.chain_id,
+                rating: player.rating,
+                games_played: player.games_played,
+                wins: player.wins,
+                losses: player.losses,
+                draws: player.draws,
+                current_streak: player.current_streak,
+            })
+            .collect();
+        LeaderboardPage {
+            entries,
+            total_count,
+        }
+    }
+
+    /// Tournaments organized by this orchestrator, optionally only those with the given status.
+    async fn tournaments(&self, status: Option<TournamentStatus>) -> Vec<&Tournament> {
+        self.tournaments
+            .values()
+            .filter(|t| status.is_none_or(|s| t.status == s))
+            .collect()
+    }
+
+    /// A tournament with its full bracket or fixture list.
+    async fn tournament(&self, tournament_id: String) -> Option<&Tournament> {
+        self.tournaments.get(&tournament_id)
+    }
+
+    /// The standings table of a league: points, round difference and head-to-head tiebreaks.
+    async fn standings(&self, tournament_id: String) -> Vec<Standing> {
+        self.tournaments
+            .get(&tournament_id)
+            .map(Tournament::standings)
+            .unwrap_or_default()
+    }
+
+    /// Open matches listed in this orchestrator's lobby, newest first, optionally only those
+    /// with the given format.
+    async fn lobby(&self, format: Option<MatchFormat>) -> Vec<&LobbyListing> {
+        let mut listings: Vec<&LobbyListing> = self
+            .lobby
+            .iter()
+            .filter(|l| format.is_none_or(|f| l.format == f))
+            .collect();
+        listings.sort_by_key(|l| std::cmp::Reverse(l.listed_at_micros));
+        listings
+    }
+
+    /// Number of players waiting in this orchestrator's matchmaking queue.
+    async fn queue_length(&self) -> usize {
+        self.matchmaking_queue.len()
+    }
+
+    /// Players waiting in this orchestrator's matchmaking queue, in pairing order.
+    async fn matchmaking_queue(&self) -> Vec<QueuedPlayer> {
+        self.matchmaking_queue
+            .iter()
+            .map(|player| QueuedPlayer {
+                chain_id: player.chain_id.clone(),
+                player_name: player.player_name.clone(),
+                format: player.format,
+                enqueued_at_micros: player.enqueued_at_micros,
+                wait_secs: self.now_micros.saturating_sub(player.enqueued_at_micros) / 1_000_000,
+            })
+            .collect()
+    }
+
+    /// Aggregate figures about the matches this orchestrator has made.
+    async fn matchmaking_stats(&self) -> MatchmakingSummary {
+        let stats = &self.matchmaking_stats;
+        let hour_ago = self
+            .now_micros
+            .saturating_sub(MatchmakingStats::RECENT_WINDOW_MICROS);
+        MatchmakingSummary {
+            queue_length: self.matchmaking_queue.len(),
+            pending_ready_checks: self.pending_ready_checks,
+            matches_made: stats.matches_made,
+            matches_last_hour: stats.matches_since(hour_ago),
+            average_time_to_match_secs: stats
+                .average_wait_micros()
+                .map(|micros| micros as f64 / 1_000_000.0),
+        }
+    }
+
     async fn last_notification(&self) -> Option<String> {
         self.last_notification.clone()
     }
+
+    /// Why the host refused this chain's most recent join request, until a match is joined.
+    async fn last_join_rejection(&self) -> Option<&JoinRejection> {
+        self.last_join_rejection.as_ref()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Enum)]
+enum LeaderboardSort {
+    Rating,
+    Wins,
+    WinRate,
+    CurrentStreak,
+    GamesPlayed,
+}
+
+#[derive(SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct RatingDetails {
+    #[graphql(flatten)]
+    rating: PlayerRating,
+    history: Vec<RatingChange>,
+}
+
+#[derive(SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct LeaderboardEntry {
+    rank: usize,
+    chain_id: String,
+    rating: i32,
+    games_played: u32,
+    wins: u32,
+    losses: u32,
+    draws: u32,
+    win_rate: f64,
+    current_streak: i32,
+}
+
+#[derive(SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct LeaderboardPage {
+    entries: Vec<LeaderboardEntry>,
+    total_count: usize,
+}
+
+#[derive(SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct QueuedPlayer {
+    chain_id: String,
+    player_name: String,
+    format: MatchFormat,
+    enqueued_at_micros: u64,
+    wait_secs: u64,
+}
+
+#[derive(SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct MatchmakingSummary {
+    queue_length: usize,
+    pending_ready_checks: usize,
+    matches_made: u64,
+    matches_last_hour: usize,
+    /// Average time matched players spent queued, if anyone was matched yet.
+    average_time_to_match_secs: Option<f64>,
+}
+
+#[derive(SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct ArchivePage {
+    matches: Vec<Game>,
+    total_count: usize,
 }
 
 struct MutationRoot {
     runtime: Arc<ServiceRuntime<SpsService>>,
+    matches: BTreeMap<String, Game>,
+    chain_id: String,
 }
 
 #[Object]
 impl MutationRoot {
-    async fn create_match(&self, host_name: String) -> String {
-        self.runtime
-            .schedule_operation(&Operation::CreateMatch { host_name: host_name.clone() });
+    #[allow(clippy::too_many_arguments)]
+    async fn create_match(
+        &self,
+        host_name: String,
+        format: Option<MatchFormat>,
+        rules: Option<GameRules>,
+        round_timeout_secs: Option<u64>,
+        stake: Option<Amount>,
+        room_code: Option<String>,
+    ) -> String {
+        self.runtime.schedule_operation(&Operation::CreateMatch {
+            host_name: host_name.clone(),
+            format,
+            rules,
+            round_timeout_secs,
+            stake,
+            room_code,
+        });
         format!("Match created by '{}'", host_name)
     }
 
-    async fn join_match(&self, host_chain_id: String, player_name: String) -> String {
+    async fn join_match(
+        &self,
+        host_chain_id: String,
+        match_id: String,
+        player_name: String,
+        stake: Option<Amount>,
+        room_code: Option<String>,
+    ) -> String {
         self.runtime.schedule_operation(&Operation::JoinMatch {
             host_chain_id: host_chain_id.clone(),
+            match_id,
             player_name: player_name.clone(),
+            stake,
+            room_code,
         });
         format!("Join request sent to {}", host_chain_id)
     }
 
-    async fn search_player(&self, orchestrator_chain_id: String, player_name: String) -> String {
+    async fn search_player(
+        &self,
+        orchestrator_chain_id: Option<String>,
+        player_name: String,
+        format: Option<MatchFormat>,
+    ) -> String {
         self.runtime.schedule_operation(&Operation::SearchPlayer {
             orchestrator_chain_id: orchestrator_chain_id.clone(),
             player_name,
+            format,
         });
-        format!("Search requested via {}", orchestrator_chain_id)
+        match orchestrator_chain_id {
+            Some(chain_id) => format!("Search requested via {}", chain_id),
+            None => "Search requested via the default orchestrator".to_string(),
+        }
     }
 
-    async fn pick_and_ready(&self, choice: Choice) -> String {
-        self.runtime
-            .schedule_operation(&Operation::PickAndReady { choice });
+    async fn respond_ready_check(&self, check_id: String, accept: bool) -> String {
+        self.runtime.schedule_operation(&Operation::RespondReadyCheck {
+            check_id: check_id.clone(),
+            accept,
+        });
+        if accept {
+            format!("Ready check {} accepted", check_id)
+        } else {
+            format!("Ready check {} declined", check_id)
+        }
+    }
+
+    async fn create_tournament(
+        &self,
+        name: String,
+        kind: Option<TournamentKind>,
+        bracket_size: u32,
+        format: Option<MatchFormat>,
+    ) -> String {
+        self.runtime.schedule_operation(&Operation::CreateTournament {
+            name: name.clone(),
+            kind,
+            bracket_size,
+            format,
+        });
+        format!("Tournament '{}' created", name)
+    }
+
+    async fn start_tournament(&self, tournament_id: String) -> String {
+        self.runtime.schedule_operation(&Operation::StartTournament {
+            tournament_id: tournament_id.clone(),
+        });
+        format!("Tournament {} started", tournament_id)
+    }
+
+    async fn play_fixtures(&self, tournament_id: String, round: u32) -> String {
+        self.runtime.schedule_operation(&Operation::PlayFixtures {
+            tournament_id: tournament_id.clone(),
+            round,
+        });
+        format!("Round {} of {} started", round, tournament_id)
+    }
+
+    async fn register_for_tournament(
+        &self,
+        orchestrator_chain_id: Option<String>,
+        tournament_id: String,
+        player_name: String,
+    ) -> String {
+        self.runtime.schedule_operation(&Operation::RegisterForTournament {
+            orchestrator_chain_id,
+            tournament_id: tournament_id.clone(),
+            player_name,
+        });
+        format!("Registration for {} sent", tournament_id)
+    }
+
+    async fn publish_match(
+        &self,
+        match_id: String,
+        orchestrator_chain_id: Option<String>,
+        name: String,
+        description: Option<String>,
+    ) -> String {
+        self.runtime.schedule_operation(&Operation::PublishMatch {
+            match_id: match_id.clone(),
+            orchestrator_chain_id,
+            name,
+            description,
+        });
+        format!("Match {} published to the lobby", match_id)
+    }
+
+    async fn cancel_search(&self, orchestrator_chain_id: Option<String>) -> String {
+        self.runtime.schedule_operation(&Operation::CancelSearch {
+            orchestrator_chain_id,
+        });
+        "Matchmaking search cancelled".to_string()
+    }
+
+    /// Commits to `choice` without revealing it. The same `salt` must be passed to
+    /// `revealChoice` once the opponent has committed too.
+    async fn pick_and_ready(&self, match_id: String, choice: Choice, salt: String) -> String {
+        let Some(game) = self.matches.get(&match_id) else {
+            return "No match".to_string();
+        };
+        let commitment =
+            choice_commitment(&game.match_id, game.round, &self.chain_id, choice, &salt);
+        self.runtime.schedule_operation(&Operation::PickAndReady {
+            match_id,
+            commitment,
+        });
         "Ready sent".to_string()
     }
 
-    async fn leave_match(&self) -> String {
-        self.runtime.schedule_operation(&Operation::LeaveMatch);
+    async fn reveal_choice(&self, match_id: String, choice: Choice, salt: String) -> String {
+        self.runtime.schedule_operation(&Operation::RevealChoice {
+            match_id,
+            choice,
+            salt,
+        });
+        "Reveal sent".to_string()
+    }
+
+    async fn claim_timeout(&self, match_id: String, claim_match: bool) -> String {
+        self.runtime.schedule_operation(&Operation::ClaimTimeout {
+            match_id,
+            claim_match,
+        });
+        "Timeout claim sent".to_string()
+    }
+
+    async fn invite_player(
+        &self,
+        match_id: String,
+        chain_id: String,
+        expires_in_secs: Option<u64>,
+    ) -> String {
+        self.runtime.schedule_operation(&Operation::InvitePlayer {
+            match_id,
+            chain_id: chain_id.clone(),
+            expires_in_secs,
+        });
+        format!("Invitation sent to {}", chain_id)
+    }
+
+    async fn accept_invite(&self, match_id: String, player_name: String) -> String {
+        self.runtime.schedule_operation(&Operation::AcceptInvite {
+            match_id: match_id.clone(),
+            player_name,
+        });
+        format!("Invitation to {} accepted", match_id)
+    }
+
+    async fn decline_invite(&self, match_id: String) -> String {
+        self.runtime.schedule_operation(&Operation::DeclineInvite {
+            match_id: match_id.clone(),
+        });
+        format!("Invitation to {} declined", match_id)
+    }
+
+    async fn request_rematch(&self, match_id: String, swap_host: Option<bool>) -> String {
+        self.runtime.schedule_operation(&Operation::RequestRematch {
+            match_id: match_id.clone(),
+            swap_host: swap_host.unwrap_or(false),
+        });
+        format!("Rematch of {} requested", match_id)
+    }
+
+    async fn accept_rematch(&self, match_id: String) -> String {
+        self.runtime.schedule_operation(&Operation::AcceptRematch {
+            match_id: match_id.clone(),
+        });
+        format!("Rematch of {} accepted", match_id)
+    }
+
+    async fn spectate(&self, host_chain_id: String) -> String {
+        self.runtime.schedule_operation(&Operation::Spectate {
+            host_chain_id: host_chain_id.clone(),
+        });
+        format!("Spectating {}", host_chain_id)
+    }
+
+    async fn stop_spectating(&self, host_chain_id: String) -> String {
+        self.runtime.schedule_operation(&Operation::StopSpectating {
+            host_chain_id: host_chain_id.clone(),
+        });
+        format!("Stopped spectating {}", host_chain_id)
+    }
+
+    async fn leave_match(&self, match_id: String) -> String {
+        self.runtime
+            .schedule_operation(&Operation::LeaveMatch { match_id });
         "Leave requested".to_string()
     }
+
+    async fn resign(&self, match_id: String) -> String {
+        self.runtime
+            .schedule_operation(&Operation::Resign { match_id });
+        "Resignation requested".to_string()
+    }
+
+    async fn offer_draw(&self, match_id: String) -> String {
+        self.runtime
+            .schedule_operation(&Operation::OfferDraw { match_id });
+        "Draw offer requested".to_string()
+    }
+
+    async fn accept_draw(&self, match_id: String) -> String {
+        self.runtime
+            .schedule_operation(&Operation::AcceptDraw { match_id });
+        "Draw acceptance requested".to_string()
+    }
 }
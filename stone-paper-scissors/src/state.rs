@@ -1,14 +1,59 @@
-use linera_sdk::views::{linera_views, RegisterView, RootView, ViewStorageContext};
-use stone_paper_scissors::{Choice, Game, MatchmakingPlayer};
+use linera_sdk::views::{
+    linera_views, LogView, MapView, RegisterView, RootView, SetView, ViewStorageContext,
+};
+use stone_paper_scissors::{
+    Game, Invitation, JoinRejection, LobbyListing, MatchmakingPlayer, MatchmakingStats,
+    PendingMatch, PlayerRating, RatingChange, ReadyCheck, RematchRequest, RoundState, Tournament,
+};
 
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct SpsState {
-    pub game: RegisterView<Option<Game>>,
-    pub my_ready: RegisterView<bool>,
-    pub opponent_ready: RegisterView<bool>,
-    pub my_choice: RegisterView<Option<Choice>>,
-    pub opponent_choice: RegisterView<Option<Choice>>,
+    pub matches: MapView<String, Game>,
+    pub rounds: MapView<String, RoundState>,
+    pub match_counter: RegisterView<u64>,
+    /// Finished and abandoned matches, oldest first.
+    pub archive: LogView<Game>,
+    /// Position of each archived match in `archive`.
+    pub archive_index: MapView<String, usize>,
+    /// Positions in `archive` of the matches played against each opponent, oldest first.
+    pub archive_by_opponent: MapView<String, Vec<usize>>,
     pub last_notification: RegisterView<Option<String>>,
+    pub last_join_rejection: RegisterView<Option<JoinRejection>>,
+    /// Host chain of each match this chain asked to join and has not heard back about.
+    pub pending_joins: MapView<String, String>,
     pub matchmaking_queue: RegisterView<Vec<MatchmakingPlayer>>,
+    pub matchmaking_stats: RegisterView<MatchmakingStats>,
+    /// Tournaments organized by this orchestrator, keyed by tournament ID.
+    pub tournaments: MapView<String, Tournament>,
+    pub tournament_counter: RegisterView<u64>,
+    /// Open matches listed in this orchestrator's public lobby, keyed by match ID.
+    pub lobby: MapView<String, LobbyListing>,
+    /// Pairings waiting on a ready check, on the orchestrator.
+    pub pending_matches: MapView<String, PendingMatch>,
+    pub ready_check_counter: RegisterView<u64>,
+    /// Ready checks this chain has been asked to answer.
+    pub ready_checks: MapView<String, ReadyCheck>,
+    /// Invitations this chain sent as a host, keyed by `<match_id>/<invitee_chain_id>`.
+    pub sent_invitations: MapView<String, Invitation>,
+    /// Invitations this chain received, keyed by match ID.
+    pub invitations: MapView<String, Invitation>,
+    /// Matches whose opponent offered this chain a draw.
+    pub draw_offers: SetView<String>,
+    /// Matches in which this chain offered a draw.
+    pub offered_draws: SetView<String>,
+    /// Rematches offered to this chain, keyed by the finished match's ID.
+    pub rematch_requests: MapView<String, RematchRequest>,
+    /// Host chains whose match events this chain subscribes to.
+    pub spectating: SetView<String>,
+    /// Latest known state of each match followed as a spectator.
+    pub spectated: MapView<String, Game>,
+    pub ratings: MapView<String, PlayerRating>,
+    /// Each rated player's most recent rating changes, oldest first. Kept apart from
+    /// `ratings` so that listing players does not read their history.
+    pub rating_history: MapView<String, Vec<RatingChange>>,
+    pub rated_matches: SetView<String>,
+    /// Pairings this orchestrator dispatched and has no result for yet, keyed by
+    /// `<host_chain_id>/<guest_chain_id>`, with how many are still outstanding.
+    pub dispatched_matches: MapView<String, u32>,
 }